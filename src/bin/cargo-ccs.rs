@@ -1,34 +1,40 @@
 use std::path::PathBuf;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, ErrorKind, Parser, Subcommand};
 use tracing_subscriber::EnvFilter;
 
-use complex_code_spotter::{Complexity, OutputFormat, SnippetsProducer};
+use complex_code_spotter::{
+    Complexity, Composite, Hierarchy, Layout, MiVariant, OutputFormat, SnippetsProducer, SpaceKind,
+    Threshold,
+};
 
 const fn thresholds_long_help() -> &'static str {
     "Threshold 0 is minimum value, thus no threshold at all.\n\
-     Threshold 100 is maximum value, thus each complexity value is not accepted.\n\n\
-   Thresholds 0 and 100 are extremes and are generally not recommended"
+     Thresholds MUST be non-negative, except for the composite metric which accepts any value.\n\
+     Maintainability index thresholds MUST be in the range of the selected variant:\n\
+     [0, 100] for visual_studio, at most 171 for original and at most 221 for sei.\n\
+     Halstead thresholds are floating-point values, e.g. halstead_bugs:0.5\n\
+     The maintainability index and the comment ratio report the code below their thresholds, e.g. comment_ratio:0.1\n\
+     A threshold can be relative to all the analyzed code:\n\
+     - pN reports the code above the N-th percentile, e.g. cognitive:p95 reports the worst 5%\n\
+     - zN reports the code N standard deviations worse than the mean, e.g. cyclomatic:z2\n\n\
+   Threshold 0 is an extreme and it is generally not recommended"
 }
 
 fn possible_values() -> String {
     format!(
         "\n       [possible values: {}, {}]",
-        Complexity::all()
+        Complexity::variants().join(", "),
+        Complexity::variants()
             .iter()
-            .map(|c| c.to_string().to_lowercase())
-            .collect::<Vec<String>>()
-            .join(", "),
-        Complexity::all()
-            .iter()
-            .map(|c| format!("{}:threshold", c.to_string().to_lowercase()))
+            .map(|c| format!("{c}:threshold"))
             .collect::<Vec<String>>()
             .join(", ")
     )
 }
 
 #[derive(Debug, PartialEq)]
-struct CliComplexity(Complexity, usize);
+struct CliComplexity(Complexity, Threshold);
 
 impl std::str::FromStr for CliComplexity {
     type Err = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -37,14 +43,14 @@ impl std::str::FromStr for CliComplexity {
         let (complexity, value) = if let Some((complexity, value)) = s.split_once(':') {
             (
                 Complexity::from_str(complexity.trim()).map_err(|_| possible_values())?,
-                value
-                    .trim()
-                    .parse::<usize>()
-                    .map_err(|_| possible_values())?,
+                Threshold::from_str(value)?,
             )
         } else {
             let complexity = Complexity::from_str(s.trim()).map_err(|_| possible_values())?;
-            (complexity, complexity.default_threshold())
+            (
+                complexity,
+                Threshold::Absolute(complexity.default_threshold()),
+            )
         };
         Ok(Self(complexity, value))
     }
@@ -73,6 +79,47 @@ struct Opts {
     /// List of complexities metrics and thresholds considered for snippets
     #[clap(long, short, default_values = &["cyclomatic:15","cognitive:15"], long_help = thresholds_long_help())]
     complexities: Vec<CliComplexity>,
+    /// Layout of the snippets, either grouped by metric or by function
+    #[clap(long, default_value = Layout::default(), possible_values = Layout::variants())]
+    layout: Layout,
+    /// Hierarchy of the snippets of nested spaces: flat, nested, leaves or
+    /// outermost
+    #[clap(long, default_value = Hierarchy::default(), possible_values = Hierarchy::variants())]
+    hierarchy: Hierarchy,
+    /// Kinds of the spaces reported as snippets, e.g. function,impl, all kinds by default.
+    /// The subspaces of the other kinds are still analyzed
+    #[clap(long, possible_values = SpaceKind::variants(), use_value_delimiter = true)]
+    space_kinds: Vec<SpaceKind>,
+    /// Exclude test code, e.g. Rust #[cfg(test)] modules and #[test] functions, JUnit @Test
+    /// methods, pytest test functions and JavaScript describe/it callbacks
+    #[clap(long)]
+    exclude_tests: bool,
+    /// Number of lines of context shown before and after each snippet
+    #[clap(long, default_value_t = 0)]
+    context_lines: usize,
+    /// Maximum number of lines of a snippet, the middle lines of longer snippets are elided
+    #[clap(long)]
+    max_lines: Option<usize>,
+    /// Report only the positions of the snippets, without their text
+    #[clap(long)]
+    no_text: bool,
+    /// List the lines which increment the cognitive and the cyclomatic complexities
+    #[clap(long)]
+    explain: bool,
+    /// List every analyzed function with all its metrics in the csv and tsv outputs,
+    /// also when it does not exceed any threshold
+    #[clap(long)]
+    all_functions: bool,
+    /// Write a single markdown, html or json report, with a summary followed by a section
+    /// for each file, instead of a file for each source file
+    #[clap(long)]
+    single_file: bool,
+    /// Variant of the maintainability index
+    #[clap(long, default_value = MiVariant::default(), possible_values = MiVariant::variants())]
+    mi_variant: MiVariant,
+    /// Formula of the composite metric, e.g. "0.5*cognitive + 0.5*cyclomatic"
+    #[clap(long)]
+    composite: Option<Composite>,
 }
 
 #[derive(Subcommand)]
@@ -94,6 +141,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         opts: Cmd::Ccs(opts),
     } = Cli::parse();
 
+    // Check the thresholds against the selected maintainability index variant.
+    for CliComplexity(complexity, threshold) in &opts.complexities {
+        if let Err(e) = threshold.validate(*complexity, opts.mi_variant) {
+            Cli::command().error(ErrorKind::InvalidValue, e).exit();
+        }
+    }
+
     let complexity = opts.complexities.iter().map(|v| v.0).collect();
     let thresholds = opts.complexities.iter().map(|v| v.1).collect();
 
@@ -128,14 +182,49 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_writer(std::io::stderr)
         .init();
 
-    SnippetsProducer::new()
+    let mut producer = SnippetsProducer::new()
         .complexities(complexity)
         .thresholds(thresholds)
+        .mi_variant(opts.mi_variant)
+        .layout(opts.layout)
+        .hierarchy(opts.hierarchy)
+        .space_kinds(opts.space_kinds)
+        .context_lines(opts.context_lines)
         .enable_write()
         .output_format(opts.output_format)
         .include(opts.include)
         .exclude(opts.exclude)
-        .run(source_path, opts.output_path)?;
+        .root(metadata.workspace_root.as_std_path());
+
+    if let Some(composite) = opts.composite {
+        producer = producer.composite(composite);
+    }
+
+    if let Some(max_lines) = opts.max_lines {
+        producer = producer.max_lines(max_lines);
+    }
+
+    if opts.no_text {
+        producer = producer.disable_text();
+    }
+
+    if opts.explain {
+        producer = producer.enable_explain();
+    }
+
+    if opts.exclude_tests {
+        producer = producer.exclude_tests();
+    }
+
+    if opts.all_functions {
+        producer = producer.enable_all_functions();
+    }
+
+    if opts.single_file {
+        producer = producer.enable_single_file();
+    }
+
+    producer.run(source_path, opts.output_path)?;
 
     Ok(())
 }
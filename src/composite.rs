@@ -0,0 +1,258 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::metrics::Complexity;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Metric(Complexity),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, metric: &dyn Fn(Complexity) -> f64) -> f64 {
+        match self {
+            Self::Number(n) => *n,
+            Self::Metric(complexity) => metric(*complexity),
+            Self::Neg(e) => -e.eval(metric),
+            Self::Add(l, r) => l.eval(metric) + r.eval(metric),
+            Self::Sub(l, r) => l.eval(metric) - r.eval(metric),
+            Self::Mul(l, r) => l.eval(metric) * r.eval(metric),
+            Self::Div(l, r) => l.eval(metric) / r.eval(metric),
+        }
+    }
+}
+
+// Recursive descent parser for formulas with the following grammar:
+//
+// expr   := term (('+' | '-') term)*
+// term   := factor (('*' | '/') factor)*
+// factor := number | metric | '-' factor | '(' expr ')'
+// number := digits ['.' digits] [('e' | 'E') ['+' | '-'] digits]
+struct Parser<'a> {
+    formula: &'a str,
+    pos: usize,
+    metrics: Vec<Complexity>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(formula: &'a str) -> Self {
+        Self {
+            formula,
+            pos: 0,
+            metrics: Vec::new(),
+        }
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::Composite(format!(
+            "{msg} at position {} of `{}`",
+            self.pos, self.formula
+        ))
+    }
+
+    // Skips whitespaces and returns the next character without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.formula[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.formula[self.pos..].chars().next()
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.formula[self.pos..];
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    // Consumes the exponent of a number, e.g. `e3` or `E-2`, when present.
+    fn exponent(&mut self) {
+        let rest = &self.formula.as_bytes()[self.pos..];
+        let digits = match rest {
+            [b'e' | b'E', b'+' | b'-', ..] => 2,
+            [b'e' | b'E', ..] => 1,
+            _ => return,
+        };
+        if rest.get(digits).is_some_and(u8::is_ascii_digit) {
+            self.pos += digits;
+            self.take_while(|c| c.is_ascii_digit());
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut expr = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = Box::new(self.term()?);
+            expr = if op == '+' {
+                Expr::Add(Box::new(expr), rhs)
+            } else {
+                Expr::Sub(Box::new(expr), rhs)
+            };
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut expr = self.factor()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            let rhs = Box::new(self.factor()?);
+            expr = if op == '*' {
+                Expr::Mul(Box::new(expr), rhs)
+            } else {
+                Expr::Div(Box::new(expr), rhs)
+            };
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.factor()?)))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("Expected `)`"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                self.take_while(|c| c.is_ascii_digit() || c == '.');
+                self.exponent();
+                let number = &self.formula[start..self.pos];
+                number
+                    .parse()
+                    .map(Expr::Number)
+                    .map_err(|_| self.error(&format!("Invalid number `{number}`")))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                let complexity = Complexity::from_str(name)
+                    .map_err(|_| self.error(&format!("Unknown metric `{name}`")))?;
+                if complexity == Complexity::Composite {
+                    return Err(self.error("A composite metric cannot contain itself"));
+                }
+                if !self.metrics.contains(&complexity) {
+                    self.metrics.push(complexity);
+                }
+                Ok(Expr::Metric(complexity))
+            }
+            Some(c) => Err(self.error(&format!("Unexpected `{c}`"))),
+            None => Err(self.error("Unexpected end of formula")),
+        }
+    }
+}
+
+/// A composite score computed as a formula over complexity metrics.
+///
+/// A formula is made of numbers, also with an exponent such as `1e3`,
+/// metric names, the `+`, `-`, `*` and `/` operators and parentheses, e.g.
+/// `0.5*cognitive + 0.3*cyclomatic + 0.2*sloc/10`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composite {
+    formula: String,
+    expr: Expr,
+    metrics: Vec<Complexity>,
+}
+
+impl Composite {
+    /// Metrics contained in the formula, in order of appearance.
+    pub fn metrics(&self) -> &[Complexity] {
+        &self.metrics
+    }
+
+    pub(crate) fn eval(&self, metric: &dyn Fn(Complexity) -> f64) -> f64 {
+        self.expr.eval(metric)
+    }
+}
+
+impl FromStr for Composite {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let expr = parser.expr()?;
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(&format!("Unexpected `{c}`")));
+        }
+        Ok(Self {
+            formula: s.trim().to_string(),
+            expr,
+            metrics: parser.metrics,
+        })
+    }
+}
+
+impl fmt::Display for Composite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.formula)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(formula: &str) -> f64 {
+        Composite::from_str(formula).unwrap().eval(&|c| match c {
+            Complexity::Cognitive => 10.,
+            Complexity::Cyclomatic => 20.,
+            Complexity::Sloc => 100.,
+            _ => 0.,
+        })
+    }
+
+    #[test]
+    fn weighted_sum() {
+        let composite =
+            Composite::from_str("0.5*cognitive + 0.3*cyclomatic + 0.2*sloc/10").unwrap();
+
+        assert_eq!(
+            composite.metrics(),
+            &[
+                Complexity::Cognitive,
+                Complexity::Cyclomatic,
+                Complexity::Sloc
+            ]
+        );
+        assert!((eval("0.5*cognitive + 0.3*cyclomatic + 0.2*sloc/10") - 13.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        assert_eq!(eval("2 + 3 * cognitive"), 32.);
+        assert_eq!(eval("(2 + 3) * cognitive"), 50.);
+        assert_eq!(eval("-cognitive + cyclomatic - 1"), 9.);
+    }
+
+    #[test]
+    fn exponents() {
+        assert_eq!(eval("1e3"), 1000.);
+        assert_eq!(eval("2.5E-1 * sloc"), 25.);
+        assert_eq!(eval("1e+2 - cognitive"), 90.);
+        assert!(Composite::from_str("1e").is_err());
+        assert!(Composite::from_str("1e-").is_err());
+    }
+
+    #[test]
+    fn invalid_formulas() {
+        assert!(Composite::from_str("").is_err());
+        assert!(Composite::from_str("0.5 * unknown").is_err());
+        assert!(Composite::from_str("(cognitive + 1").is_err());
+        assert!(Composite::from_str("cognitive cyclomatic").is_err());
+        assert!(Composite::from_str("composite * 2").is_err());
+    }
+}
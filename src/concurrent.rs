@@ -7,7 +7,7 @@ use crossbeam::channel::{unbounded, Receiver, Sender};
 use globset::GlobSet;
 use walkdir::{DirEntry, WalkDir};
 
-use crate::Result;
+use crate::{Error, Result};
 
 type ProcFilesFunction<Config> = dyn Fn(PathBuf, &Config) -> Result<()> + Send + Sync;
 
@@ -28,8 +28,13 @@ struct JobItem<Config> {
 type JobReceiver<Config> = Receiver<Option<JobItem<Config>>>;
 type JobSender<Config> = Sender<Option<JobItem<Config>>>;
 
-fn consumer<Config, ProcFiles>(receiver: JobReceiver<Config>, func: Arc<ProcFiles>)
-where
+type ErrorSender = Sender<(PathBuf, Error)>;
+
+fn consumer<Config, ProcFiles>(
+    receiver: JobReceiver<Config>,
+    func: Arc<ProcFiles>,
+    errors: ErrorSender,
+) where
     ProcFiles: Fn(PathBuf, &Config) -> Result<()> + Send + Sync,
 {
     while let Ok(job) = receiver.recv() {
@@ -40,8 +45,11 @@ where
         let job = job.unwrap();
         let path = job.path.clone();
 
+        // Send the error back, so the caller decides how to handle it.
         if let Err(err) = func(job.path, &job.cfg) {
-            eprintln!("{} for file {:?}", err, path);
+            if errors.send((path, err)).is_err() {
+                break;
+            }
         }
     }
 }
@@ -136,6 +144,7 @@ pub(crate) enum ConcurrentErrors {
 }
 
 /// Data related to files.
+#[derive(Clone)]
 pub(crate) struct FilesData {
     /// Kind of files included in a search.
     pub include: GlobSet,
@@ -158,7 +167,7 @@ impl<Config: 'static + Send + Sync> ConcurrentRunner<Config> {
     ///
     /// * `num_jobs` - Number of jobs utilized to process files concurrently.
     /// * `proc_files` - Function that processes each file found during
-    ///    the search.
+    ///   the search.
     pub(crate) fn new<ProcFiles>(num_jobs: usize, proc_files: ProcFiles) -> Self
     where
         ProcFiles: 'static + Fn(PathBuf, &Config) -> Result<()> + Send + Sync,
@@ -177,15 +186,19 @@ impl<Config: 'static + Send + Sync> ConcurrentRunner<Config> {
     ///
     /// * `config` - Information used to process a file.
     /// * `files_data` - Information about the files to be included or excluded
-    ///    from a search more the number of paths considered in the search.
+    ///   from a search more the number of paths considered in the search.
+    ///
+    /// Returns the files which could not be processed, together with
+    /// their errors.
     pub(crate) fn run(
         self,
         config: Config,
         files_data: FilesData,
-    ) -> Result<HashMap<String, Vec<PathBuf>>> {
+    ) -> Result<Vec<(PathBuf, Error)>> {
         let cfg = Arc::new(config);
 
         let (sender, receiver) = unbounded();
+        let (error_sender, error_receiver) = unbounded();
 
         let producer = {
             let sender = sender.clone();
@@ -211,11 +224,12 @@ impl<Config: 'static + Send + Sync> ConcurrentRunner<Config> {
         for i in 0..self.num_jobs {
             let receiver = receiver.clone();
             let proc_files = proc_files.clone();
+            let error_sender = error_sender.clone();
 
             let t = match thread::Builder::new()
                 .name(format!("Consumer {}", i))
                 .spawn(move || {
-                    consumer(receiver, proc_files);
+                    consumer(receiver, proc_files, error_sender);
                 }) {
                 Ok(receiver) => receiver,
                 Err(e) => return Err(ConcurrentErrors::Thread(e.to_string()).into()),
@@ -244,6 +258,8 @@ impl<Config: 'static + Send + Sync> ConcurrentRunner<Config> {
             }
         }
 
-        all_files
+        all_files?;
+
+        Ok(error_receiver.try_iter().collect())
     }
 }
@@ -29,6 +29,12 @@ pub enum Error {
     /// Mutability access failures.
     #[error("Mutability failure: {0}")]
     Mutability(String),
+    /// Invalid or missing composite formula.
+    #[error("Composite formula error: {0}")]
+    Composite(String),
+    /// Invalid threshold.
+    #[error("Invalid threshold: {0}")]
+    InvalidThreshold(String),
     /// Less thresholds than complexity metrics.
     #[error("Each complexity metric MUST have a threshold.")]
     Thresholds,
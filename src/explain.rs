@@ -0,0 +1,389 @@
+use serde::Serialize;
+use tree_sitter::Node;
+
+use crate::metrics::Complexity;
+
+// Rules used by rust-code-analysis to compute the cognitive and the
+// cyclomatic complexities of a language.
+//
+// Node kinds are matched by name, so the same increments computed by
+// rust-code-analysis are reported for each node. Recursion is not counted
+// by rust-code-analysis, so recursive calls never cause an increment.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Rules {
+    Rust,
+    Python,
+    Cpp,
+    Java,
+    // Kind of the parent of an `else if`, which does not increase the
+    // nesting level.
+    Javascript { else_if: &'static str },
+}
+
+/// Increment of a complexity metric caused by a line of code.
+#[derive(Debug, Clone, Serialize)]
+pub struct IncrementData {
+    /// Incremented metric.
+    pub metric: Complexity,
+    /// Line of the construct which causes the increment.
+    pub line: usize,
+    /// Value of the increment.
+    pub increment: usize,
+    /// Construct which causes the increment, together with its nesting
+    /// penalty, if any.
+    pub reason: String,
+}
+
+// Nesting state which a node inherits from its parent.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Scope {
+    // Nesting level of the conditional structures.
+    nesting: usize,
+    // Number of enclosing functions.
+    depth: usize,
+    // Number of enclosing lambdas.
+    lambda: usize,
+}
+
+// Computes the increments of the cognitive and the cyclomatic complexities
+// of a single space.
+#[derive(Debug, Default)]
+pub(crate) struct Explainer {
+    // Last nesting penalty, applied to increments which do not compute
+    // their own penalty.
+    penalty: usize,
+    // First operator of the current sequence of boolean operators.
+    boolean_op: Option<&'static str>,
+    pub(crate) increments: Vec<IncrementData>,
+}
+
+#[inline(always)]
+fn line(node: &Node) -> usize {
+    node.start_position().row + 1
+}
+
+// Whether an `if` is an `else if`, i.e. its parent is of a certain kind.
+fn is_else_if(node: &Node, parent: &str) -> bool {
+    node.parent().is_some_and(|node| node.kind() == parent)
+}
+
+// Whether a node has an ancestor of a certain kind.
+fn has_ancestor(node: &Node, kind: &str) -> bool {
+    let mut node = *node;
+    while let Some(parent) = node.parent() {
+        if parent.kind() == kind {
+            return true;
+        }
+        node = parent;
+    }
+    false
+}
+
+// Counts the ancestors of some kinds, stopping at the first ancestor of
+// one of the stop kinds.
+fn count_ancestors(node: &Node, kinds: &[&str], stops: &[&str]) -> usize {
+    let mut count = 0;
+    let mut node = *node;
+    while let Some(parent) = node.parent() {
+        if kinds.contains(&parent.kind()) {
+            count += 1;
+        } else if stops.contains(&parent.kind()) {
+            break;
+        }
+        node = parent;
+    }
+    count
+}
+
+impl Explainer {
+    // Every space starts with a cyclomatic complexity of one.
+    pub(crate) fn new(node: &Node) -> Self {
+        let mut explainer = Self::default();
+        explainer.add(Complexity::Cyclomatic, line(node), 1, "entry point".into());
+        explainer
+    }
+
+    fn add(&mut self, metric: Complexity, line: usize, increment: usize, reason: String) {
+        if increment > 0 {
+            self.increments.push(IncrementData {
+                metric,
+                line,
+                increment,
+                reason,
+            });
+        }
+    }
+
+    // Increments the cognitive complexity of a structure which also
+    // increases the nesting level.
+    fn nested(&mut self, node: &Node, scope: &mut Scope, reason: &str) {
+        self.penalty = scope.nesting + scope.depth + scope.lambda;
+        let reason = if self.penalty > 0 {
+            format!("{reason}, nesting +{}", self.penalty)
+        } else {
+            reason.to_string()
+        };
+        self.add(Complexity::Cognitive, line(node), self.penalty + 1, reason);
+        scope.nesting += 1;
+        self.boolean_op = None;
+    }
+
+    fn structural(&mut self, node: &Node, reason: &str) {
+        self.add(Complexity::Cognitive, line(node), 1, reason.to_string());
+    }
+
+    // A sequence of equal boolean operators increments the cognitive
+    // complexity only once.
+    fn booleans(&mut self, node: &Node, operators: &[&str]) {
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            let operator = child.kind();
+            if !operators.contains(&operator) {
+                continue;
+            }
+            match self.boolean_op {
+                Some(previous) if previous == operator => {}
+                Some(_) => self.structural(&child, &format!("boolean operator {operator}")),
+                None => {
+                    self.boolean_op = Some(operator);
+                    self.structural(&child, &format!("boolean operator {operator}"));
+                }
+            }
+        }
+    }
+
+    // Visits a node, returning the nesting state inherited by its children.
+    pub(crate) fn visit(&mut self, rules: Rules, node: &Node, scope: Scope) -> Scope {
+        let mut scope = scope;
+        match rules {
+            Rules::Rust => self.rust(node, &mut scope),
+            Rules::Python => self.python(node, &mut scope),
+            Rules::Cpp => self.cpp(node, &mut scope),
+            Rules::Javascript { else_if } => self.javascript(node, &mut scope, else_if),
+            // The cognitive complexity is not computed for Java.
+            Rules::Java => {}
+        }
+        self.cyclomatic(rules, node);
+        scope
+    }
+
+    fn rust(&mut self, node: &Node, scope: &mut Scope) {
+        match node.kind() {
+            "if_expression" if !is_else_if(node, "else_clause") => self.nested(node, scope, "if"),
+            "for_expression" => self.nested(node, scope, "for"),
+            "while_expression" => self.nested(node, scope, "while"),
+            "match_expression" => self.nested(node, scope, "match"),
+            "else" => self.structural(node, "else"),
+            kind @ ("break_expression" | "continue_expression")
+                if node
+                    .child(1)
+                    .is_some_and(|label| label.kind() == "loop_label") =>
+            {
+                let jump = kind.trim_end_matches("_expression");
+                self.structural(node, &format!("{jump} to label"));
+            }
+            "unary_expression" => self.boolean_op = Some(node.kind()),
+            "binary_expression" => self.booleans(node, &["&&", "||"]),
+            "function_item" => {
+                scope.nesting = 0;
+                if has_ancestor(node, "function_item") {
+                    scope.depth += 1;
+                }
+            }
+            "closure_expression" => scope.lambda += 1,
+            _ => {}
+        }
+    }
+
+    fn python(&mut self, node: &Node, scope: &mut Scope) {
+        match node.kind() {
+            "if_statement" => self.nested(node, scope, "if"),
+            "for_statement" => self.nested(node, scope, "for"),
+            "while_statement" => self.nested(node, scope, "while"),
+            "conditional_expression" => self.nested(node, scope, "conditional expression"),
+            "elif_clause" => {
+                self.structural(node, "elif");
+                self.boolean_op = None;
+            }
+            "else_clause" => self.structural(node, "else"),
+            "finally_clause" => self.structural(node, "finally"),
+            "except_clause" => {
+                scope.nesting += 1;
+                self.add(
+                    Complexity::Cognitive,
+                    line(node),
+                    self.penalty + 1,
+                    "except".into(),
+                );
+            }
+            "expression_list" | "expression_statement" | "tuple" => self.boolean_op = None,
+            "not_operator" => self.boolean_op = Some(node.kind()),
+            "boolean_operator" => {
+                if count_ancestors(node, &["boolean_operator"], &["lambda"]) == 0 {
+                    let lambdas = count_ancestors(
+                        node,
+                        &["lambda"],
+                        &[
+                            "expression_list",
+                            "if_statement",
+                            "for_statement",
+                            "while_statement",
+                        ],
+                    );
+                    self.add(
+                        Complexity::Cognitive,
+                        line(node),
+                        lambdas,
+                        "boolean operator in lambda".into(),
+                    );
+                }
+                self.booleans(node, &["and", "or"]);
+            }
+            "lambda" if node.is_named() => scope.lambda += 1,
+            "function_definition" if has_ancestor(node, "function_definition") => {
+                scope.depth += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn cpp(&mut self, node: &Node, scope: &mut Scope) {
+        match node.kind() {
+            "if_statement" if !is_else_if(node, "if_statement") => self.nested(node, scope, "if"),
+            "for_statement" => self.nested(node, scope, "for"),
+            "while_statement" => self.nested(node, scope, "while"),
+            "do_statement" => self.nested(node, scope, "do"),
+            "switch_statement" => self.nested(node, scope, "switch"),
+            "catch_clause" => self.nested(node, scope, "catch"),
+            "goto_statement" => self.structural(node, "goto"),
+            "else" => self.structural(node, "else"),
+            "unary_expression" => self.boolean_op = Some(node.kind()),
+            "binary_expression" => self.booleans(node, &["&&", "||"]),
+            "lambda_expression" => scope.lambda += 1,
+            _ => {}
+        }
+    }
+
+    fn javascript(&mut self, node: &Node, scope: &mut Scope, else_if: &str) {
+        match node.kind() {
+            "if_statement" if !is_else_if(node, else_if) => self.nested(node, scope, "if"),
+            "for_statement" | "for_in_statement" => self.nested(node, scope, "for"),
+            "while_statement" => self.nested(node, scope, "while"),
+            "do_statement" => self.nested(node, scope, "do"),
+            "switch_statement" => self.nested(node, scope, "switch"),
+            "catch_clause" => self.nested(node, scope, "catch"),
+            "ternary_expression" => self.nested(node, scope, "conditional expression"),
+            "else" => self.structural(node, "else"),
+            "expression_statement" => self.boolean_op = None,
+            "unary_expression" => self.boolean_op = Some(node.kind()),
+            "binary_expression" => self.booleans(node, &["&&", "||"]),
+            "function_declaration" => {
+                scope.nesting = 0;
+                scope.lambda = 0;
+                if has_ancestor(node, "function_declaration") {
+                    scope.depth += 1;
+                }
+            }
+            "arrow_function" => scope.lambda += 1,
+            _ => {}
+        }
+    }
+
+    // Each decision point increments the cyclomatic complexity by one.
+    fn cyclomatic(&mut self, rules: Rules, node: &Node) {
+        let kind = node.kind();
+        let decision = match rules {
+            Rules::Rust => matches!(
+                kind,
+                "if" | "for" | "while" | "loop" | "match_arm" | "?" | "&&" | "||"
+            ),
+            Rules::Python => {
+                matches!(
+                    kind,
+                    "if" | "elif" | "for" | "while" | "except" | "with" | "assert" | "and" | "or"
+                ) || (kind == "else"
+                    && node.parent().is_some_and(|parent| {
+                        parent.kind() == "else_clause"
+                            && parent.parent().is_some_and(|parent| {
+                                matches!(parent.kind(), "for_statement" | "while_statement")
+                            })
+                    }))
+            }
+            Rules::Cpp => matches!(
+                kind,
+                "if" | "for" | "while" | "case" | "catch" | "conditional_expression" | "&&" | "||"
+            ),
+            Rules::Java | Rules::Javascript { .. } => matches!(
+                kind,
+                "if" | "for" | "while" | "case" | "catch" | "ternary_expression" | "&&" | "||"
+            ),
+        };
+        if decision {
+            let reason = match kind {
+                "match_arm" => "match arm".to_string(),
+                "?" => "? operator".to_string(),
+                "&&" | "||" | "and" | "or" => format!("boolean operator {kind}"),
+                "conditional_expression" | "ternary_expression" => {
+                    "conditional expression".to_string()
+                }
+                _ => kind.to_string(),
+            };
+            self.add(Complexity::Cyclomatic, line(node), 1, reason);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use rust_code_analysis::{get_function_spaces, LANG};
+
+    use crate::snippets::AnalyzedFile;
+    use crate::space::Space;
+
+    use super::*;
+
+    // Sum of the increments of a metric.
+    fn sum(increments: &[IncrementData], metric: Complexity) -> f64 {
+        increments
+            .iter()
+            .filter(|increment| increment.metric == metric)
+            .map(|increment| increment.increment as f64)
+            .sum()
+    }
+
+    // Checks that the increments of a space and of its subspaces add up to
+    // the values computed by rust-code-analysis.
+    fn check_increments(space: &Space, path: &Path) {
+        let increments = &space.syntax.increments;
+        assert_eq!(
+            sum(increments, Complexity::Cognitive),
+            space.space.metrics.cognitive.cognitive(),
+            "cognitive of `{}` in {path:?}",
+            space.qualified_name
+        );
+        assert_eq!(
+            sum(increments, Complexity::Cyclomatic),
+            space.space.metrics.cyclomatic.cyclomatic(),
+            "cyclomatic of `{}` in {path:?}",
+            space.qualified_name
+        );
+        for subspace in space.subspaces() {
+            check_increments(&subspace, path);
+        }
+    }
+
+    #[test]
+    fn seahorse_increments() {
+        for entry in fs::read_dir("data/seahorse/src").unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            let space =
+                get_function_spaces(&LANG::Rust, source.as_bytes().to_vec(), &path, None).unwrap();
+            let file = AnalyzedFile::new(path.clone(), source, LANG::Rust, space);
+            check_increments(&Space::unit(&file.space, &file.syntax), &path);
+        }
+    }
+}
@@ -5,20 +5,40 @@
 //!
 //! - Cyclomatic
 //! - Cognitive
+//! - Halstead (volume, difficulty, effort and estimated bugs)
+//! - Maintainability Index
+//! - Lines of code (SLOC, PLOC, LLOC, CLOC, blank lines and comment ratio)
+//! - Number of arguments and of exit points
+//! - Maximum nesting depth
+//! - ABC (Assignments, Branches and Conditions)
+//! - Class-level metrics (WMC, NOM, NPM and NPA)
+//! - A composite score computed as a formula over the metrics above
 //!
 //! When the value associated to each of the metrics exceeds a preset threshold,
-//! a snippet of code is automatically extracted.
+//! a snippet of code is automatically extracted. For metrics where lower
+//! values are worse, such as the Maintainability Index, a snippet is extracted
+//! when the value falls below the threshold.
 
+mod composite;
 mod concurrent;
 mod error;
+mod explain;
 mod metrics;
 mod non_utf8;
 mod output;
+mod report;
 mod snippets;
+mod space;
+mod suppression;
+mod syntax;
+mod threshold;
 
-pub use metrics::Complexity;
+pub use composite::Composite;
+pub use metrics::{Complexity, MiVariant};
 pub use output::OutputFormat;
-pub use snippets::Snippets;
+pub use snippets::{Hierarchy, Layout, Snippets};
+pub use space::SpaceKind;
+pub use threshold::Threshold;
 
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -26,11 +46,16 @@ use std::thread::available_parallelism;
 
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use rust_code_analysis::{get_function_spaces, guess_language, read_file_with_eol};
+use tracing::{debug, warn};
 
 use concurrent::{ConcurrentRunner, FilesData};
 use error::{Error, Result};
+use metrics::MetricsOptions;
 use non_utf8::encode_to_utf8;
-use snippets::get_code_snippets;
+use report::{ReportConfig, Tables};
+use snippets::{
+    get_code_snippets, get_metrics_values, AnalyzedFile, FileSpaces, SnippetsOptions, SpaceFilter,
+};
 
 #[derive(Debug)]
 struct Parameters {
@@ -39,13 +64,27 @@ struct Parameters {
     include: Vec<String>,
     exclude: Vec<String>,
     complexities: Vec<Complexity>,
-    thresholds: Vec<usize>,
+    thresholds: Vec<Threshold>,
+    mi_variant: MiVariant,
+    composite: Option<Composite>,
+    layout: Layout,
+    hierarchy: Hierarchy,
+    space_kinds: Vec<SpaceKind>,
+    exclude_tests: bool,
+    context_lines: usize,
+    max_lines: Option<usize>,
+    text: bool,
+    explain: bool,
+    all_functions: bool,
+    single_file: bool,
+    root: Option<PathBuf>,
 }
 
 /// Produce snippets of complex code for a source file.
 ///
 /// If no parameters are set, the producer uses *cyclomatic* with a
-/// threshold of 15 as default metric.
+/// threshold of 15 as default metric, and the *Visual Studio* variant
+/// of the maintainability index.
 /// Write on files is disabled by default, but when enabled,
 /// *markdown* is the output format.
 #[derive(Debug)]
@@ -66,7 +105,20 @@ impl SnippetsProducer {
             include: Vec::new(),
             exclude: Vec::new(),
             complexities: vec![Complexity::Cyclomatic],
-            thresholds: vec![15],
+            thresholds: vec![Threshold::Absolute(15.)],
+            mi_variant: MiVariant::VisualStudio,
+            composite: None,
+            layout: Layout::Metric,
+            hierarchy: Hierarchy::Flat,
+            space_kinds: Vec::new(),
+            exclude_tests: false,
+            context_lines: 0,
+            max_lines: None,
+            text: true,
+            explain: false,
+            all_functions: false,
+            single_file: false,
+            root: None,
         })
     }
 
@@ -89,8 +141,131 @@ impl SnippetsProducer {
     }
 
     /// Sets the respective thresholds associated to each complexity metric.
-    pub fn thresholds(mut self, thresholds: Vec<usize>) -> Self {
-        self.0.thresholds = thresholds.into_iter().map(|v| v.min(100)).collect();
+    ///
+    /// A threshold can be an absolute value or a value relative to the
+    /// distribution of a metric over all the analyzed code, such as a
+    /// percentile or a z-score.
+    pub fn thresholds<T: Into<Threshold>>(mut self, thresholds: Vec<T>) -> Self {
+        self.0.thresholds = thresholds.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the variant used to compute the maintainability index.
+    pub fn mi_variant(mut self, mi_variant: MiVariant) -> Self {
+        self.0.mi_variant = mi_variant;
+        self
+    }
+
+    /// Sets the formula used to compute the composite metric.
+    pub fn composite(mut self, composite: Composite) -> Self {
+        self.0.composite = Some(composite);
+        self
+    }
+
+    /// Sets the layout of the snippets.
+    ///
+    /// The metric layout, which is the default one, groups snippets by
+    /// metric, while the function layout reports each function once
+    /// together with the values of all the metrics.
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.0.layout = layout;
+        self
+    }
+
+    /// Sets the hierarchy of the snippets of nested spaces.
+    ///
+    /// The flat hierarchy, which is the default one, reports every space
+    /// independently, while the other hierarchies nest the snippets of the
+    /// subspaces in the snippet of their space, or report only the
+    /// innermost or the outermost spaces.
+    pub fn hierarchy(mut self, hierarchy: Hierarchy) -> Self {
+        self.0.hierarchy = hierarchy;
+        self
+    }
+
+    /// Sets the kinds of the spaces which can be extracted as snippets.
+    ///
+    /// Spaces of the other kinds are not reported, but their subspaces are
+    /// still analyzed, e.g. the closures of a function are reported when
+    /// only closures are selected. All kinds are selected by default.
+    pub fn space_kinds(mut self, space_kinds: Vec<SpaceKind>) -> Self {
+        self.0.space_kinds = space_kinds;
+        self
+    }
+
+    /// Excludes test code from the analysis.
+    ///
+    /// Rust test code is contained in `#[cfg(test)]` modules, also when
+    /// `test` is within `all` or `any`, or marked by `#[test]`-like
+    /// attributes, e.g. `#[tokio::test]`. Tests of the other languages are
+    /// detected heuristically: JUnit `@Test`-like methods, pytest
+    /// `test` functions and `Test` classes, and the callbacks of JavaScript
+    /// `describe`, `it` and `test` calls.
+    pub fn exclude_tests(mut self) -> Self {
+        self.0.exclude_tests = true;
+        self
+    }
+
+    /// Sets the number of lines of context shown before and after each
+    /// snippet.
+    pub fn context_lines(mut self, context_lines: usize) -> Self {
+        self.0.context_lines = context_lines;
+        self
+    }
+
+    /// Sets the maximum number of lines of a snippet.
+    ///
+    /// The middle lines of longer snippets are elided, keeping the first
+    /// and the last lines.
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.0.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Disables the text of the snippets, so only their positions are
+    /// reported.
+    pub fn disable_text(mut self) -> Self {
+        self.0.text = false;
+        self
+    }
+
+    /// Enables the explain mode, which lists the lines incrementing the
+    /// cognitive and the cyclomatic complexities of each snippet.
+    ///
+    /// Recursion is not counted by the cognitive complexity, so recursive
+    /// calls are never listed.
+    pub fn enable_explain(mut self) -> Self {
+        self.0.explain = true;
+        self
+    }
+
+    /// Enables the list of every analyzed function, together with all its
+    /// metrics, also when it does not exceed any threshold.
+    ///
+    /// The csv and tsv outputs report a row for each function instead of a
+    /// row for each snippet.
+    pub fn enable_all_functions(mut self) -> Self {
+        self.0.all_functions = true;
+        self
+    }
+
+    /// Enables a single report for all the analyzed files, with a summary
+    /// followed by a section for each file, instead of a file for each
+    /// source file.
+    ///
+    /// It applies to the markdown, html and json outputs.
+    pub fn enable_single_file(mut self) -> Self {
+        self.0.single_file = true;
+        self
+    }
+
+    /// Sets the directory which the paths of the source files are relative
+    /// to in the fingerprints and in the reports.
+    ///
+    /// Source files outside of this directory keep their path. By default,
+    /// it is the current directory.
+    pub fn root<P: AsRef<Path>>(mut self, root: P) -> Self {
+        self.0.root = Some(root.as_ref().to_path_buf());
         self
     }
 
@@ -124,14 +299,36 @@ impl SnippetsProducer {
             return Err(Error::Thresholds);
         }
 
-        // Create container for snippets.
+        // Check that each threshold is in the range accepted by its metric.
+        for (complexity, threshold) in self.0.complexities.iter().zip(&self.0.thresholds) {
+            threshold.validate(*complexity, self.0.mi_variant)?;
+        }
+
+        // Check that the composite metric has a formula.
+        if self.0.complexities.contains(&Complexity::Composite) && self.0.composite.is_none() {
+            return Err(Error::Composite(
+                "The composite metric MUST have a formula".to_string(),
+            ));
+        }
+
+        // Create containers for snippets and for the analyzed spaces.
         let snippets_context = Arc::new(Mutex::new(Vec::new()));
+        let spaces_context = Arc::new(Mutex::new(Vec::new()));
 
         let num_jobs = available_parallelism()?.get();
-        let cfg = SnippetsConfig {
-            complexities: self.0.complexities,
-            thresholds: self.0.thresholds,
-            snippets: snippets_context.clone(),
+
+        let root = match self.0.root {
+            Some(root) => root,
+            None => std::env::current_dir()?,
+        };
+
+        let filter = SpaceFilter {
+            kinds: self.0.space_kinds,
+            exclude_tests: self.0.exclude_tests,
+        };
+        let options = MetricsOptions {
+            mi_variant: self.0.mi_variant,
+            composite: self.0.composite,
         };
         let files_data = FilesData {
             include: Self::mk_globset(self.0.include),
@@ -139,28 +336,129 @@ impl SnippetsProducer {
             path: source_path.as_ref().to_path_buf(),
         };
 
+        // Collect the values of the metrics with a relative threshold over
+        // all the analyzed code.
+        let relative_complexities: Vec<Complexity> = self
+            .0
+            .complexities
+            .iter()
+            .zip(&self.0.thresholds)
+            .filter(|(_, threshold)| threshold.is_relative())
+            .map(|(complexity, _)| *complexity)
+            .collect();
+        // Only the values are kept, so the files are analyzed again to
+        // extract the snippets.
+        let values = if relative_complexities.is_empty() {
+            Vec::new()
+        } else {
+            let values_context =
+                Arc::new(Mutex::new(vec![Vec::new(); relative_complexities.len()]));
+            let cfg = ValuesConfig {
+                complexities: relative_complexities,
+                options: options.clone(),
+                filter: filter.clone(),
+                values: values_context.clone(),
+            };
+            // Files which cannot be analyzed are reported when extracting
+            // the snippets.
+            let errors = ConcurrentRunner::new(num_jobs, collect_file_values)
+                .run(cfg, files_data.clone())?;
+            check_errors(errors, false)?;
+            Arc::try_unwrap(values_context)
+                .map_err(|_| Error::Mutability("Unable to get computed values".to_string()))?
+                .into_inner()?
+        };
+
+        // Compute the absolute thresholds.
+        let mut values = values.into_iter();
+        let complexity_thresholds = self
+            .0
+            .complexities
+            .into_iter()
+            .zip(self.0.thresholds)
+            .map(|(complexity, threshold)| match threshold {
+                Threshold::Absolute(value) => (complexity, value),
+                _ => {
+                    // Values are in the same order of the relative thresholds.
+                    let mut values = values.next().unwrap_or_default();
+                    let value = threshold.resolve(complexity, &mut values);
+                    debug!("{complexity} threshold {threshold} is {value}");
+                    (complexity, value)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Tables are created before the analysis, so the rows of each file
+        // are written as soon as it has been analyzed.
+        let tables = if self.0.write {
+            self.0
+                .output_format
+                .create_tables(output_path.as_ref(), self.0.all_functions)?
+        } else {
+            None
+        };
+        let reports_spaces = self.0.output_format.reports_spaces();
+
+        let cfg = SnippetsConfig {
+            complexity_thresholds: complexity_thresholds.clone(),
+            metrics: options,
+            options: SnippetsOptions {
+                layout: self.0.layout,
+                hierarchy: self.0.hierarchy,
+                filter,
+                context_lines: self.0.context_lines,
+                max_lines: self.0.max_lines,
+                text: self.0.text,
+                explain: self.0.explain,
+                spaces: self.0.all_functions || reports_spaces,
+                all_metrics: self.0.all_functions,
+                root: root.clone(),
+            },
+            snippets: snippets_context.clone(),
+            spaces: spaces_context.clone(),
+            keep_spaces: reports_spaces,
+            tables,
+        };
+
         // Extracts snippets concurrently.
-        ConcurrentRunner::new(num_jobs, extract_file_snippets).run(cfg, files_data)?;
+        let errors = ConcurrentRunner::new(num_jobs, extract_file_snippets).run(cfg, files_data)?;
+        check_errors(errors, true)?;
 
         // Retrieve snippets.
-        let snippets_context = Arc::try_unwrap(snippets_context)
+        let mut snippets_context = Arc::try_unwrap(snippets_context)
             .map_err(|_| Error::Mutability("Unable to get computed snippets".to_string()))?
             .into_inner()?;
+        let spaces = Arc::try_unwrap(spaces_context)
+            .map_err(|_| Error::Mutability("Unable to get analyzed spaces".to_string()))?
+            .into_inner()?;
+
+        // Files with only unused suppressions are written, but they are not
+        // complex.
+        let clean = !snippets_context.iter().any(Snippets::is_complex);
+
+        // Write files. The formats which report every analyzed space are
+        // written also when the code is clean.
+        if self.0.write && (!snippets_context.is_empty() || reports_spaces) {
+            let config = ReportConfig {
+                thresholds: &complexity_thresholds,
+                all_functions: self.0.all_functions,
+                single_file: self.0.single_file,
+                spaces: &spaces,
+                root: &root,
+            };
+            self.0
+                .output_format
+                .write_format(output_path, &snippets_context, &config)?;
+        }
 
         // If there are no snippets, print a message informing that the code is
         // clean.
-        if snippets_context.is_empty() {
+        if clean {
             println!("Congratulations! Your code is clean, it does not have any complexity!");
             return Ok(None);
         }
 
-        // Write files.
-        if self.0.write {
-            self.0
-                .output_format
-                .write_format(output_path, &snippets_context)?;
-        }
-
+        snippets_context.retain(Snippets::is_complex);
         Ok(Some(snippets_context))
     }
 
@@ -180,12 +478,26 @@ impl SnippetsProducer {
 
 #[derive(Debug)]
 struct SnippetsConfig {
-    complexities: Vec<Complexity>,
-    thresholds: Vec<usize>,
+    complexity_thresholds: Vec<(Complexity, f64)>,
+    metrics: MetricsOptions,
+    options: SnippetsOptions,
     snippets: Arc<Mutex<Vec<Snippets>>>,
+    spaces: Arc<Mutex<Vec<FileSpaces>>>,
+    // Whether the analyzed spaces are kept for the reports written at the
+    // end of the analysis.
+    keep_spaces: bool,
+    tables: Option<Tables>,
 }
 
-fn extract_file_snippets(source_path: PathBuf, cfg: &SnippetsConfig) -> Result<()> {
+#[derive(Debug)]
+struct ValuesConfig {
+    complexities: Vec<Complexity>,
+    options: MetricsOptions,
+    filter: SpaceFilter,
+    values: Arc<Mutex<Vec<Vec<f64>>>>,
+}
+
+fn analyze_file(source_path: PathBuf) -> Result<AnalyzedFile> {
     // Read source file an return it as a sequence of bytes.
     let source_file_bytes = read_file_with_eol(&source_path)?.ok_or(Error::WrongContent)?;
 
@@ -211,28 +523,102 @@ fn extract_file_snippets(source_path: PathBuf, cfg: &SnippetsConfig) -> Result<(
     )
     .ok_or(Error::NoSpaces)?;
 
-    // Get code snippets for each metric
-    let snippets = get_code_snippets(
-        &spaces,
-        language.into(),
+    Ok(AnalyzedFile::new(
         source_path,
-        source_file.as_ref(),
+        source_file,
+        language,
+        spaces,
+    ))
+}
+
+fn collect_file_values(source_path: PathBuf, cfg: &ValuesConfig) -> Result<()> {
+    let file = analyze_file(source_path)?;
+
+    // Get the values of each metric for the spaces of the file.
+    let mut values = vec![Vec::new(); cfg.complexities.len()];
+    get_metrics_values(
+        &file,
         &cfg.complexities,
-        &cfg.thresholds,
+        &cfg.options,
+        &cfg.filter,
+        &mut values,
+    );
+
+    cfg.values
+        .as_ref()
+        .lock()?
+        .iter_mut()
+        .zip(values)
+        .for_each(|(all_values, values)| all_values.extend(values));
+
+    Ok(())
+}
+
+fn extract_file_snippets(source_path: PathBuf, cfg: &SnippetsConfig) -> Result<()> {
+    let file = analyze_file(source_path)?;
+
+    // Malformed suppressions do not suppress anything, so they are likely
+    // mistakes, e.g. a misspelled metric.
+    for suppression in &file.suppressions {
+        if let Some(error) = &suppression.error {
+            warn!(
+                "Malformed suppression at {}:{}: {error}",
+                file.source_path.display(),
+                suppression.line
+            );
+        }
+    }
+
+    // Get code snippets for each metric
+    let (snippets, spaces) = get_code_snippets(
+        &file,
+        &cfg.complexity_thresholds,
+        &cfg.metrics,
+        &cfg.options,
     );
 
+    // Table rows are streamed, so they are not kept in memory.
+    if let Some(tables) = &cfg.tables {
+        let config = ReportConfig {
+            thresholds: &cfg.complexity_thresholds,
+            all_functions: cfg.options.all_metrics,
+            single_file: false,
+            spaces: &[],
+            root: &cfg.options.root,
+        };
+        tables.write_file(snippets.as_ref(), spaces.as_ref(), &config)?;
+    }
+
     // If there are snippets, output file/files in the chosen format.
     if let Some(snippets) = snippets {
         cfg.snippets.as_ref().lock()?.push(snippets);
     }
+    // Spaces are recorded apart, so they do not change the returned snippets.
+    if let Some(spaces) = spaces.filter(|_| cfg.keep_spaces) {
+        cfg.spaces.as_ref().lock()?.push(spaces);
+    }
 
     Ok(())
 }
 
+// Returns the errors which do not depend on a single file, such as a
+// poisoned lock. The files which cannot be analyzed are skipped, and they
+// are optionally reported.
+fn check_errors(errors: Vec<(PathBuf, Error)>, report: bool) -> Result<()> {
+    for (path, error) in errors {
+        match error {
+            Error::Concurrent(_) | Error::Mutability(_) => return Err(error),
+            _ if report => eprintln!("{error} for file {path:?}"),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::{
-        fs::{create_dir_all, read_dir, remove_dir_all, File},
+        fs::{create_dir_all, read_dir, read_to_string, remove_dir_all, write, File},
         io::BufReader,
         path::Path,
     };
@@ -245,7 +631,7 @@ mod test {
         output_path: &'a Path,
         compare_path: &'a Path,
         complexities: Vec<Complexity>,
-        thresholds: Vec<usize>,
+        thresholds: Vec<f64>,
     }
 
     impl<'a> Config<'a> {
@@ -259,13 +645,53 @@ mod test {
             }
         }
 
-        fn metrics(mut self, complexities: Vec<Complexity>, thresholds: Vec<usize>) -> Self {
+        fn metrics(mut self, complexities: Vec<Complexity>, thresholds: Vec<f64>) -> Self {
             self.complexities = complexities;
             self.thresholds = thresholds;
             self
         }
     }
 
+    // Directory removed when dropped, so it is removed also when a test
+    // fails.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn create(path: PathBuf) -> Self {
+            create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        // Creates a directory, unique for each test, in the temporary
+        // directory of the system.
+        fn new(name: &str) -> Self {
+            Self::create(std::env::temp_dir().join(format!(
+                "complex-code-spotter-{}-{name}",
+                std::process::id()
+            )))
+        }
+
+        // Writes some source files, returning their directory.
+        fn sources(&self, files: &[(&str, &str)]) -> PathBuf {
+            let path = self.0.join("src");
+            create_dir_all(&path).unwrap();
+            for (name, source) in files {
+                write(path.join(name), source).unwrap();
+            }
+            path
+        }
+
+        fn output(&self) -> PathBuf {
+            self.0.join("output")
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = remove_dir_all(&self.0);
+        }
+    }
+
     fn read_file(path: &Path) -> std::io::Result<serde_json::Value> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
@@ -275,44 +701,52 @@ mod test {
         Ok(json_file)
     }
 
+    // Sorted paths of the files contained in a directory.
+    fn sorted_paths(dir: &Path) -> Vec<std::path::PathBuf> {
+        let mut paths = read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
     fn run_comparator(cfg: Config) {
-        // Create output directory.
-        create_dir_all(cfg.output_path).unwrap();
+        // Create output directory, removed at the end of the comparison.
+        let _output = TempDir::create(cfg.output_path.to_path_buf());
 
         // Produce snippets.
         SnippetsProducer::new()
             .complexities(cfg.complexities)
             .thresholds(cfg.thresholds)
             .output_format(OutputFormat::Json)
+            .enable_write()
             .run(cfg.source_path, cfg.output_path)
             .unwrap();
 
         // Retrieve output and comparison JSON paths.
-        let output_paths = read_dir(cfg.output_path).unwrap();
-        let compare_paths = read_dir(cfg.compare_path).unwrap();
+        let output_paths = sorted_paths(&cfg.output_path.join("json"));
+        let compare_paths = sorted_paths(cfg.compare_path);
 
         // Compare output and comparison JSON files.
-        output_paths
-            .zip(compare_paths)
-            .for_each(|(output, compare)| {
-                let json_output = read_file(&output.unwrap().path()).unwrap();
-                let compare_output = read_file(&compare.unwrap().path()).unwrap();
-                // Catch the panic when test is going to fail.
-                let result = std::panic::catch_unwind(|| {
-                    assert_eq!(json_output, compare_output);
-                });
-                if let Err(result) = result {
-                    // Remove output directory.
-                    remove_dir_all(cfg.output_path).unwrap();
-                    // Show the error.
-                    panic!("{:?}", result);
-                } else {
-                    assert!(result.is_ok());
-                }
-            });
-
-        // Remove output directory.
-        remove_dir_all(cfg.output_path).unwrap();
+        assert_eq!(
+            output_paths
+                .iter()
+                .map(|path| path.file_name())
+                .collect::<Vec<_>>(),
+            compare_paths
+                .iter()
+                .map(|path| path.file_name())
+                .collect::<Vec<_>>()
+        );
+        for (output, compare) in output_paths.iter().zip(&compare_paths) {
+            let json_output = read_file(output).unwrap();
+            let compare_output = read_file(compare).unwrap();
+            assert_eq!(
+                json_output, compare_output,
+                "{output:?} differs from {compare:?}"
+            );
+        }
     }
 
     #[test]
@@ -325,7 +759,7 @@ mod test {
         )
         .metrics(
             vec![Complexity::Cyclomatic, Complexity::Cognitive],
-            vec![15, 15],
+            vec![15., 15.],
         );
 
         // Run comparator.
@@ -341,9 +775,225 @@ mod test {
         )
         .metrics(
             vec![Complexity::Cyclomatic, Complexity::Cognitive],
-            vec![8, 8],
+            vec![8., 8.],
         );
 
         run_comparator(cfg);
     }
+
+    // Functions whose cyclomatic complexities are 1, 2 and 3.
+    const COMPLEX: &str = "fn simple() {}
+
+fn sign(x: i32) -> i32 {
+    if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+fn classify(x: i32) -> i32 {
+    if x > 10 {
+        2
+    } else if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+";
+
+    const CLEAN: &str = "fn clean() -> i32 {
+    0
+}
+";
+
+    // Qualified names of the snippets of a metric.
+    fn names(snippets: &[Snippets], complexity: Complexity) -> Vec<&str> {
+        snippets
+            .iter()
+            .filter_map(|s| s.snippets.get(&complexity))
+            .flatten()
+            .map(|v| v.location.qualified_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn invalid_configurations() {
+        // The configuration is rejected before analyzing any file.
+        let dir = TempDir::new("invalid_configurations");
+        let run = |complexity, threshold| {
+            SnippetsProducer::new()
+                .complexities(vec![complexity])
+                .thresholds(vec![threshold])
+                .run(dir.0.join("src"), dir.output())
+        };
+        assert!(matches!(
+            run(Complexity::Composite, 10.),
+            Err(Error::Composite(_))
+        ));
+        assert!(matches!(
+            run(Complexity::Cognitive, -1.),
+            Err(Error::InvalidThreshold(_))
+        ));
+    }
+
+    #[test]
+    fn relative_thresholds() {
+        let dir = TempDir::new("relative_thresholds");
+        let source_path = dir.sources(&[("complex.rs", COMPLEX), ("clean.rs", CLEAN)]);
+        let run = |threshold| {
+            SnippetsProducer::new()
+                .complexities(vec![Complexity::Cyclomatic])
+                .thresholds(vec![threshold])
+                .run(source_path.clone(), dir.output())
+                .unwrap()
+        };
+
+        // The lowest value is the minimum complexity of all the files, so
+        // only the simplest functions are not reported.
+        let snippets = run(Threshold::Percentile(0.)).unwrap();
+        assert_eq!(
+            names(&snippets, Complexity::Cyclomatic),
+            ["fn sign", "fn classify"]
+        );
+        // No value exceeds the highest one.
+        assert!(run(Threshold::Percentile(100.)).is_none());
+    }
+
+    #[test]
+    fn write_reports() {
+        let dir = TempDir::new("write_reports");
+        let source_path = dir.sources(&[("complex.rs", COMPLEX), ("clean.rs", CLEAN)]);
+        let output_path = dir.output();
+        let snippets = SnippetsProducer::new()
+            .complexities(vec![Complexity::Cyclomatic])
+            .thresholds(vec![1.])
+            .output_format(OutputFormat::All)
+            .enable_write()
+            .run(source_path, output_path.clone())
+            .unwrap()
+            .unwrap();
+        let read = |name| read_to_string(output_path.join(name)).unwrap();
+
+        // Only the files with snippets are returned.
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(
+            names(&snippets, Complexity::Cyclomatic),
+            ["fn sign", "fn classify"]
+        );
+
+        // A file for each file with snippets, named after its path.
+        for (dir, extension) in [("markdown", "md"), ("html", "html"), ("json", "json")] {
+            let files = sorted_paths(&output_path.join(dir))
+                .into_iter()
+                .filter_map(|path| path.file_name()?.to_str().map(str::to_owned))
+                .collect::<Vec<_>>();
+            let named = |name: &str| {
+                files
+                    .iter()
+                    .filter(|file| file.ends_with(&format!("src_{name}.{extension}")))
+                    .count()
+            };
+            assert_eq!((named("complex"), named("clean")), (1, 0));
+        }
+        // One finding for each snippet, whose details are checked by the
+        // report unit tests.
+        let log: serde_json::Value =
+            serde_json::from_str(&read("complex-code-spotter.sarif")).unwrap();
+        assert_eq!(log["runs"][0]["results"].as_array().map(Vec::len), Some(2));
+        let issues: Vec<serde_json::Value> =
+            serde_json::from_str(&read("complex-code-spotter-codeclimate.json")).unwrap();
+        assert_eq!(issues.len(), 2);
+        let checkstyle = read("complex-code-spotter-checkstyle.xml");
+        assert_eq!(checkstyle.matches("<file ").count(), 1);
+        assert_eq!(checkstyle.matches("<error ").count(), 2);
+        for table in ["complex-code-spotter.csv", "complex-code-spotter.tsv"] {
+            assert_eq!(read(table).lines().count(), 1 + 2);
+        }
+        // Every analyzed function is a test case, also in clean files.
+        assert!(read("complex-code-spotter-junit.xml").contains(r#"tests="4" failures="2""#));
+    }
+
+    #[test]
+    fn write_clean_reports() {
+        let dir = TempDir::new("write_clean_reports");
+        let source_path = dir.sources(&[("complex.rs", COMPLEX), ("clean.rs", CLEAN)]);
+        let output_path = dir.output();
+        let run = |output_format| {
+            SnippetsProducer::new()
+                .complexities(vec![Complexity::Cyclomatic])
+                .thresholds(vec![10.])
+                .output_format(output_format)
+                .enable_all_functions()
+                .enable_write()
+                .run(source_path.clone(), output_path.clone())
+                .unwrap()
+        };
+
+        // Clean code is listed, but no snippets are returned.
+        assert!(run(OutputFormat::Tsv).is_none());
+        let table = read_to_string(output_path.join("complex-code-spotter.tsv")).unwrap();
+        let functions = table
+            .lines()
+            .filter(|row| row.contains("\tfunction\t"))
+            .count();
+        assert_eq!(functions, 4);
+
+        assert!(run(OutputFormat::JUnit).is_none());
+        let report = read_to_string(output_path.join("complex-code-spotter-junit.xml")).unwrap();
+        assert!(report.contains(r#"tests="4" failures="0""#));
+    }
+
+    #[test]
+    fn write_single_file() {
+        let dir = TempDir::new("write_single_file");
+        let source_path = dir.sources(&[("complex.rs", COMPLEX), ("clean.rs", CLEAN)]);
+        let output_path = dir.output();
+        SnippetsProducer::new()
+            .complexities(vec![Complexity::Cyclomatic, Complexity::Cognitive])
+            .thresholds(vec![1., 1.])
+            .output_format(OutputFormat::All)
+            .enable_single_file()
+            .enable_write()
+            .run(source_path, output_path.clone())
+            .unwrap()
+            .unwrap();
+
+        // A single report replaces the directory of each format.
+        for dir in ["markdown", "html", "json"] {
+            assert!(!output_path.join(dir).exists());
+        }
+        let report = read_file(&output_path.join("complex-code-spotter.json")).unwrap();
+        let summary = &report["summary"];
+        assert_eq!(summary["files"].as_array().map(Vec::len), Some(1));
+        assert_eq!(report["files"].as_array().map(Vec::len), Some(1));
+        assert_eq!(summary["metrics"][0]["metric"], "Cyclomatic");
+        assert_eq!(summary["metrics"][0]["snippets"], 2);
+
+        let markdown = read_to_string(output_path.join("complex-code-spotter.md")).unwrap();
+        assert!(markdown.starts_with("# Summary"));
+        assert!(markdown.contains("\n## Cyclomatic\n"));
+        assert!(output_path.join("complex-code-spotter.html").exists());
+    }
+
+    #[test]
+    fn unused_suppressions() {
+        let dir = TempDir::new("unused_suppressions");
+        let source = format!("// ccs:allow(cyclomatic)\n{CLEAN}");
+        let source_path = dir.sources(&[("clean.rs", &source)]);
+        let output_path = dir.output();
+        let snippets = SnippetsProducer::new()
+            .output_format(OutputFormat::Json)
+            .enable_write()
+            .run(source_path, output_path.clone())
+            .unwrap();
+
+        // Unused suppressions are written, but the code is still clean.
+        assert!(snippets.is_none());
+        let files = sorted_paths(&output_path.join("json"));
+        assert_eq!(files.len(), 1);
+        let report = read_file(&files[0]).unwrap();
+        assert_eq!(report["unused_suppressions"][0]["line"], 1);
+    }
 }
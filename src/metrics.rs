@@ -1,17 +1,46 @@
+use std::ops::RangeInclusive;
+
 use arg_enum_proc_macro::ArgEnum;
-use rust_code_analysis::FuncSpace;
+use rust_code_analysis::{FuncSpace, SpaceKind};
 use serde::Serialize;
 
+use crate::composite::Composite;
+use crate::syntax::SpaceSyntax;
+
+// Data of a space, besides the metrics computed by rust-code-analysis,
+// employed by some metrics.
+struct Context<'a> {
+    syntax: &'a SpaceSyntax,
+    options: &'a MetricsOptions,
+}
+
 trait ComplexityChecker {
-    fn check(space: &FuncSpace, threshold: usize) -> Option<usize>;
+    // Whether lower metric values denote a more complex space.
+    const LOWER_IS_WORSE: bool = false;
+    // Whether a subspace might exceed the threshold when its space does
+    // not, so subspaces are always visited.
+    const ALWAYS_VISIT: bool = false;
+
+    fn value(space: &FuncSpace, context: &Context) -> f64;
+
+    // Returns the metric value of a space when the space, or one of its
+    // subspaces, might exceed the threshold.
+    fn check(space: &FuncSpace, context: &Context, threshold: f64) -> Option<f64> {
+        let value = Self::value(space, context);
+        (Self::ALWAYS_VISIT || value > threshold).then_some(value)
+    }
 }
 
 struct Cyclomatic;
 
 impl ComplexityChecker for Cyclomatic {
-    fn check(space: &FuncSpace, threshold: usize) -> Option<usize> {
-        let value = space.metrics.cyclomatic.cyclomatic() as usize;
-        (value > threshold || space.metrics.cyclomatic.cyclomatic_max() as usize > threshold)
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.cyclomatic.cyclomatic()
+    }
+
+    fn check(space: &FuncSpace, context: &Context, threshold: f64) -> Option<f64> {
+        let value = Self::value(space, context);
+        (value > threshold || space.metrics.cyclomatic.cyclomatic_max() > threshold)
             .then_some(value)
     }
 }
@@ -19,15 +48,357 @@ impl ComplexityChecker for Cyclomatic {
 struct Cognitive;
 
 impl ComplexityChecker for Cognitive {
-    fn check(space: &FuncSpace, threshold: usize) -> Option<usize> {
-        let value = space.metrics.cognitive.cognitive() as usize;
-        (value > threshold || space.metrics.cognitive.cognitive_max() as usize > threshold)
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.cognitive.cognitive()
+    }
+
+    fn check(space: &FuncSpace, context: &Context, threshold: f64) -> Option<f64> {
+        let value = Self::value(space, context);
+        (value > threshold || space.metrics.cognitive.cognitive_max() > threshold).then_some(value)
+    }
+}
+
+// Arguments of a function or of a closure.
+struct Nargs;
+
+impl ComplexityChecker for Nargs {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.nargs.fn_args() + space.metrics.nargs.closure_args()
+    }
+
+    fn check(space: &FuncSpace, context: &Context, threshold: f64) -> Option<f64> {
+        let value = Self::value(space, context);
+        (value > threshold
+            || space.metrics.nargs.fn_args_max() > threshold
+            || space.metrics.nargs.closure_args_max() > threshold)
             .then_some(value)
     }
 }
 
-/// Supported complexities metrics.
+struct Nexits;
+
+impl ComplexityChecker for Nexits {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.nexits.exit()
+    }
+
+    fn check(space: &FuncSpace, context: &Context, threshold: f64) -> Option<f64> {
+        let value = Self::value(space, context);
+        (value > threshold || space.metrics.nexits.exit_max() > threshold).then_some(value)
+    }
+}
+
+// ABC values of a space are the sum of the values of all its subspaces, so
+// subspaces are visited only when the space exceeds the threshold.
+
+struct Abc;
+
+impl ComplexityChecker for Abc {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.abc.magnitude_sum()
+    }
+}
+
+struct AbcAssignments;
+
+impl ComplexityChecker for AbcAssignments {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.abc.assignments_sum()
+    }
+}
+
+struct AbcBranches;
+
+impl ComplexityChecker for AbcBranches {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.abc.branches_sum()
+    }
+}
+
+struct AbcConditions;
+
+impl ComplexityChecker for AbcConditions {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.abc.conditions_sum()
+    }
+}
+
+// Class-level metrics are computed on every space, but snippets are
+// extracted only for classes, implementations and traits, which might be
+// nested anywhere, so subspaces are always visited.
+
+// Methods of a class-like space, i.e. its function subspaces.
+pub(crate) fn methods(space: &FuncSpace) -> impl Iterator<Item = &FuncSpace> {
+    space
+        .spaces
+        .iter()
+        .filter(|space| space.kind == SpaceKind::Function)
+}
+
+// Weighted methods per class, where each method is weighted by its
+// cyclomatic complexity. Only the methods defined with a body are weighted,
+// since declared methods have no complexity in the class.
+struct Wmc;
+
+impl ComplexityChecker for Wmc {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        methods(space)
+            .map(|method| method.metrics.cyclomatic.cyclomatic())
+            .sum()
+    }
+}
+
+// Methods are counted on the syntax tree for Rust and C++ code, also when
+// they are only declared, like the public methods. Otherwise, they are the
+// function subspaces.
+struct Nom;
+
+impl ComplexityChecker for Nom {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, context: &Context) -> f64 {
+        context.syntax.members.map_or_else(
+            || methods(space).count() as f64,
+            |members| members.methods as f64,
+        )
+    }
+}
+
+// Public methods and attributes counted by rust-code-analysis, only for Java
+// code. They are counted on the syntax tree for Rust and C++ code.
+struct Npm;
+
+impl ComplexityChecker for Npm {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, context: &Context) -> f64 {
+        context.syntax.members.map_or_else(
+            || space.metrics.npm.class_npm() + space.metrics.npm.interface_npm(),
+            |members| members.public_methods as f64,
+        )
+    }
+}
+
+struct Npa;
+
+impl ComplexityChecker for Npa {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, context: &Context) -> f64 {
+        context.syntax.members.map_or_else(
+            || space.metrics.npa.class_npa() + space.metrics.npa.interface_npa(),
+            |members| members.public_attributes as f64,
+        )
+    }
+}
+
+// The nesting depth of a space does not consider its subspaces, so
+// subspaces are always visited.
+struct Nesting;
+
+impl ComplexityChecker for Nesting {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(_: &FuncSpace, context: &Context) -> f64 {
+        context.syntax.nesting.depth as f64
+    }
+}
+
+// Components of a composite score are not monotonic, so subspaces are
+// always visited. The score is undefined without a formula.
+struct CompositeScore;
+
+impl ComplexityChecker for CompositeScore {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, context: &Context) -> f64 {
+        context
+            .options
+            .composite
+            .as_ref()
+            .map_or(f64::NAN, |composite| {
+                composite
+                    .eval(&|complexity| complexity.compute(space, context.syntax, context.options))
+            })
+    }
+}
+
+// Halstead metrics of a space are computed on the operators and operands of
+// all its subspaces, but they are not monotonic, so subspaces are always
+// visited.
+
+struct HalsteadVolume;
+
+impl ComplexityChecker for HalsteadVolume {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.halstead.volume()
+    }
+}
+
+struct HalsteadDifficulty;
+
+impl ComplexityChecker for HalsteadDifficulty {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.halstead.difficulty()
+    }
+}
+
+struct HalsteadEffort;
+
+impl ComplexityChecker for HalsteadEffort {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.halstead.effort()
+    }
+}
+
+struct HalsteadBugs;
+
+impl ComplexityChecker for HalsteadBugs {
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.halstead.bugs()
+    }
+}
+
+// Lines of a space include the lines of all its subspaces, so subspaces are
+// visited only when the space exceeds the threshold.
+
+struct Sloc;
+
+impl ComplexityChecker for Sloc {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.loc.sloc()
+    }
+}
+
+struct Ploc;
+
+impl ComplexityChecker for Ploc {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.loc.ploc()
+    }
+}
+
+struct Lloc;
+
+impl ComplexityChecker for Lloc {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.loc.lloc()
+    }
+}
+
+struct Cloc;
+
+impl ComplexityChecker for Cloc {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.loc.cloc()
+    }
+}
+
+struct Blank;
+
+impl ComplexityChecker for Blank {
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        space.metrics.loc.blank()
+    }
+}
+
+// Minimum number of instruction lines of a space with a comment ratio.
+const MIN_COMMENT_RATIO_PLOC: f64 = 5.;
+
+// Ratio between comment lines and instruction lines.
+//
+// The ratio of a space with only a few instruction lines, e.g. a getter,
+// is not meaningful, so its value is undefined and the space is never
+// reported.
+struct CommentRatio;
+
+impl ComplexityChecker for CommentRatio {
+    const LOWER_IS_WORSE: bool = true;
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, _: &Context) -> f64 {
+        let ploc = space.metrics.loc.ploc();
+        if ploc < MIN_COMMENT_RATIO_PLOC {
+            f64::NAN
+        } else {
+            space.metrics.loc.cloc() / ploc
+        }
+    }
+}
+
+// The maintainability index of a space is computed on the whole space, so
+// a well-maintainable space might contain a poorly-maintainable subspace.
+
+struct MaintainabilityIndex;
+
+impl ComplexityChecker for MaintainabilityIndex {
+    const LOWER_IS_WORSE: bool = true;
+    const ALWAYS_VISIT: bool = true;
+
+    fn value(space: &FuncSpace, context: &Context) -> f64 {
+        match context.options.mi_variant {
+            MiVariant::Original => space.metrics.mi.mi_original(),
+            MiVariant::Sei => space.metrics.mi.mi_sei(),
+            MiVariant::VisualStudio => space.metrics.mi.mi_visual_studio(),
+        }
+    }
+}
+
+/// Supported variants of the maintainability index.
 #[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum MiVariant {
+    /// Original formula.
+    #[arg_enum(name = "original")]
+    Original,
+    /// Formula employed by the Software Engineering Institute (SEI).
+    #[arg_enum(name = "sei")]
+    Sei,
+    /// Formula employed by Microsoft Visual Studio.
+    #[arg_enum(name = "visual_studio")]
+    VisualStudio,
+}
+
+impl MiVariant {
+    /// Default maintainability index variant.
+    pub const fn default() -> &'static str {
+        "visual_studio"
+    }
+
+    /// Range of the values of a variant.
+    ///
+    /// The original and the SEI formulas might produce negative values,
+    /// while the Visual Studio formula is normalized to the [0, 100] range.
+    pub const fn range(&self) -> RangeInclusive<f64> {
+        match self {
+            Self::Original => RangeInclusive::new(f64::MIN, 171.),
+            // The comments term adds at most 50 to the original formula.
+            Self::Sei => RangeInclusive::new(f64::MIN, 221.),
+            Self::VisualStudio => RangeInclusive::new(0., 100.),
+        }
+    }
+}
+
+/// Options used to compute the complexity metrics.
+#[derive(Debug, Clone)]
+pub(crate) struct MetricsOptions {
+    /// Maintainability index variant.
+    pub(crate) mi_variant: MiVariant,
+    /// Formula of the composite metric.
+    pub(crate) composite: Option<Composite>,
+}
+
+/// Supported complexities metrics.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub enum Complexity {
     /// Cyclomatic metric.
     #[arg_enum(name = "cyclomatic")]
@@ -35,25 +406,766 @@ pub enum Complexity {
     /// Cognitive metric.
     #[arg_enum(name = "cognitive")]
     Cognitive,
+    /// Halstead volume metric.
+    #[arg_enum(name = "halstead_volume")]
+    HalsteadVolume,
+    /// Halstead difficulty metric.
+    #[arg_enum(name = "halstead_difficulty")]
+    HalsteadDifficulty,
+    /// Halstead effort metric.
+    #[arg_enum(name = "halstead_effort")]
+    HalsteadEffort,
+    /// Halstead estimated number of delivered bugs.
+    #[arg_enum(name = "halstead_bugs")]
+    HalsteadBugs,
+    /// Maintainability index metric.
+    ///
+    /// Lower values denote a less maintainable code.
+    #[arg_enum(name = "maintainability_index", alias = "mi")]
+    MaintainabilityIndex,
+    /// Source lines of code.
+    #[arg_enum(name = "sloc")]
+    Sloc,
+    /// Physical lines of code, i.e. instruction lines.
+    #[arg_enum(name = "ploc")]
+    Ploc,
+    /// Logical lines of code, i.e. statements.
+    #[arg_enum(name = "lloc")]
+    Lloc,
+    /// Comment lines.
+    #[arg_enum(name = "cloc")]
+    Cloc,
+    /// Blank lines.
+    #[arg_enum(name = "blank")]
+    Blank,
+    /// Ratio between comment lines and instruction lines.
+    ///
+    /// Lower values denote a less documented code.
+    #[arg_enum(name = "comment_ratio")]
+    CommentRatio,
+    /// Number of arguments of a function or of a closure.
+    #[arg_enum(name = "nargs")]
+    Nargs,
+    /// Number of exit points of a function or of a closure.
+    #[arg_enum(name = "nexits")]
+    Nexits,
+    /// Maximum nesting depth of the blocks of a function.
+    #[arg_enum(name = "nesting")]
+    Nesting,
+    /// ABC magnitude metric.
+    ///
+    /// It is computed on the Assignments, Branches and Conditions of a code.
+    #[arg_enum(name = "abc", alias = "abc_magnitude")]
+    Abc,
+    /// Assignments of the ABC metric.
+    #[arg_enum(name = "abc_assignments")]
+    AbcAssignments,
+    /// Branches of the ABC metric.
+    #[arg_enum(name = "abc_branches")]
+    AbcBranches,
+    /// Conditions of the ABC metric.
+    #[arg_enum(name = "abc_conditions")]
+    AbcConditions,
+    /// Weighted methods per class metric.
+    ///
+    /// Sum of the cyclomatic complexities of the methods of a class.
+    #[arg_enum(name = "wmc")]
+    Wmc,
+    /// Number of methods of a class.
+    #[arg_enum(name = "nom")]
+    Nom,
+    /// Number of public methods of a class.
+    #[arg_enum(name = "npm")]
+    Npm,
+    /// Number of public attributes of a class.
+    #[arg_enum(name = "npa")]
+    Npa,
+    /// Composite score computed as a formula over other metrics.
+    #[arg_enum(name = "composite")]
+    Composite,
 }
 
 impl Complexity {
     /// Default threshold for a metric.
-    pub const fn default_threshold(&self) -> usize {
+    pub const fn default_threshold(&self) -> f64 {
+        match self {
+            Self::Cyclomatic => 15.,
+            Self::Cognitive => 15.,
+            Self::HalsteadVolume => 1000.,
+            Self::HalsteadDifficulty => 30.,
+            Self::HalsteadEffort => 30000.,
+            Self::HalsteadBugs => 0.5,
+            Self::MaintainabilityIndex => 20.,
+            Self::Sloc => 100.,
+            Self::Ploc => 80.,
+            Self::Lloc => 50.,
+            Self::Cloc => 50.,
+            Self::Blank => 30.,
+            Self::CommentRatio => 0.1,
+            Self::Nargs => 7.,
+            Self::Nexits => 10.,
+            Self::Nesting => 4.,
+            Self::Abc => 17.,
+            Self::AbcAssignments => 10.,
+            Self::AbcBranches => 10.,
+            Self::AbcConditions => 10.,
+            Self::Wmc => 50.,
+            Self::Nom => 20.,
+            Self::Npm => 15.,
+            Self::Npa => 5.,
+            Self::Composite => 15.,
+        }
+    }
+
+    /// Name of a metric, the same name used to select it.
+    pub const fn name(&self) -> &'static str {
         match self {
-            Self::Cyclomatic => 15,
-            Self::Cognitive => 15,
+            Self::Cyclomatic => "cyclomatic",
+            Self::Cognitive => "cognitive",
+            Self::HalsteadVolume => "halstead_volume",
+            Self::HalsteadDifficulty => "halstead_difficulty",
+            Self::HalsteadEffort => "halstead_effort",
+            Self::HalsteadBugs => "halstead_bugs",
+            Self::MaintainabilityIndex => "maintainability_index",
+            Self::Sloc => "sloc",
+            Self::Ploc => "ploc",
+            Self::Lloc => "lloc",
+            Self::Cloc => "cloc",
+            Self::Blank => "blank",
+            Self::CommentRatio => "comment_ratio",
+            Self::Nargs => "nargs",
+            Self::Nexits => "nexits",
+            Self::Nesting => "nesting",
+            Self::Abc => "abc",
+            Self::AbcAssignments => "abc_assignments",
+            Self::AbcBranches => "abc_branches",
+            Self::AbcConditions => "abc_conditions",
+            Self::Wmc => "wmc",
+            Self::Nom => "nom",
+            Self::Npm => "npm",
+            Self::Npa => "npa",
+            Self::Composite => "composite",
         }
     }
+
+    /// Short description of a metric.
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Self::Cyclomatic => "Cyclomatic complexity",
+            Self::Cognitive => "Cognitive complexity",
+            Self::HalsteadVolume => "Halstead volume",
+            Self::HalsteadDifficulty => "Halstead difficulty",
+            Self::HalsteadEffort => "Halstead effort",
+            Self::HalsteadBugs => "Halstead estimated number of delivered bugs",
+            Self::MaintainabilityIndex => "Maintainability index",
+            Self::Sloc => "Source lines of code",
+            Self::Ploc => "Physical lines of code",
+            Self::Lloc => "Logical lines of code",
+            Self::Cloc => "Comment lines",
+            Self::Blank => "Blank lines",
+            Self::CommentRatio => "Ratio between comment lines and instruction lines",
+            Self::Nargs => "Number of arguments",
+            Self::Nexits => "Number of exit points",
+            Self::Nesting => "Maximum nesting depth",
+            Self::Abc => "ABC magnitude",
+            Self::AbcAssignments => "Assignments of the ABC metric",
+            Self::AbcBranches => "Branches of the ABC metric",
+            Self::AbcConditions => "Conditions of the ABC metric",
+            Self::Wmc => "Weighted methods per class",
+            Self::Nom => "Number of methods",
+            Self::Npm => "Number of public methods",
+            Self::Npa => "Number of public attributes",
+            Self::Composite => "Composite score",
+        }
+    }
+
     /// All complexity metrics.
     pub const fn all() -> &'static [Complexity] {
-        &[Self::Cyclomatic, Self::Cognitive]
+        &[
+            Self::Cyclomatic,
+            Self::Cognitive,
+            Self::HalsteadVolume,
+            Self::HalsteadDifficulty,
+            Self::HalsteadEffort,
+            Self::HalsteadBugs,
+            Self::MaintainabilityIndex,
+            Self::Sloc,
+            Self::Ploc,
+            Self::Lloc,
+            Self::Cloc,
+            Self::Blank,
+            Self::CommentRatio,
+            Self::Nargs,
+            Self::Nexits,
+            Self::Nesting,
+            Self::Abc,
+            Self::AbcAssignments,
+            Self::AbcBranches,
+            Self::AbcConditions,
+            Self::Wmc,
+            Self::Nom,
+            Self::Npm,
+            Self::Npa,
+            Self::Composite,
+        ]
+    }
+
+    /// Whether lower values of a metric denote a more complex code.
+    pub const fn lower_is_worse(&self) -> bool {
+        match self {
+            Self::Cyclomatic => Cyclomatic::LOWER_IS_WORSE,
+            Self::Cognitive => Cognitive::LOWER_IS_WORSE,
+            Self::HalsteadVolume => HalsteadVolume::LOWER_IS_WORSE,
+            Self::HalsteadDifficulty => HalsteadDifficulty::LOWER_IS_WORSE,
+            Self::HalsteadEffort => HalsteadEffort::LOWER_IS_WORSE,
+            Self::HalsteadBugs => HalsteadBugs::LOWER_IS_WORSE,
+            Self::MaintainabilityIndex => MaintainabilityIndex::LOWER_IS_WORSE,
+            Self::Sloc => Sloc::LOWER_IS_WORSE,
+            Self::Ploc => Ploc::LOWER_IS_WORSE,
+            Self::Lloc => Lloc::LOWER_IS_WORSE,
+            Self::Cloc => Cloc::LOWER_IS_WORSE,
+            Self::Blank => Blank::LOWER_IS_WORSE,
+            Self::CommentRatio => CommentRatio::LOWER_IS_WORSE,
+            Self::Nargs => Nargs::LOWER_IS_WORSE,
+            Self::Nexits => Nexits::LOWER_IS_WORSE,
+            Self::Nesting => Nesting::LOWER_IS_WORSE,
+            Self::Abc => Abc::LOWER_IS_WORSE,
+            Self::AbcAssignments => AbcAssignments::LOWER_IS_WORSE,
+            Self::AbcBranches => AbcBranches::LOWER_IS_WORSE,
+            Self::AbcConditions => AbcConditions::LOWER_IS_WORSE,
+            Self::Wmc => Wmc::LOWER_IS_WORSE,
+            Self::Nom => Nom::LOWER_IS_WORSE,
+            Self::Npm => Npm::LOWER_IS_WORSE,
+            Self::Npa => Npa::LOWER_IS_WORSE,
+            Self::Composite => CompositeScore::LOWER_IS_WORSE,
+        }
     }
 
-    pub(crate) fn value(&self, space: &FuncSpace, threshold: usize) -> Option<usize> {
+    /// Whether a metric is computed only on classes, implementations
+    /// and traits.
+    pub const fn is_class_metric(&self) -> bool {
+        matches!(self, Self::Wmc | Self::Nom | Self::Npm | Self::Npa)
+    }
+
+    // Whether snippets can be extracted for a space of a certain kind.
+    pub(crate) fn applies_to(&self, kind: SpaceKind) -> bool {
+        !self.is_class_metric()
+            || matches!(
+                kind,
+                SpaceKind::Class
+                    | SpaceKind::Struct
+                    | SpaceKind::Trait
+                    | SpaceKind::Impl
+                    | SpaceKind::Interface
+            )
+    }
+
+    /// Whether a metric value exceeds a threshold.
+    ///
+    /// For metrics where lower values are worse, a value exceeds a
+    /// threshold when it falls below it.
+    pub fn exceeds(&self, value: f64, threshold: f64) -> bool {
+        if self.lower_is_worse() {
+            value < threshold
+        } else {
+            value > threshold
+        }
+    }
+
+    /// Range of the absolute thresholds accepted for a metric, where the
+    /// range of the maintainability index depends on its variant.
+    pub const fn threshold_range(&self, mi_variant: MiVariant) -> RangeInclusive<f64> {
+        match self {
+            Self::MaintainabilityIndex => mi_variant.range(),
+            // A formula might produce any value.
+            Self::Composite => RangeInclusive::new(f64::MIN, f64::MAX),
+            _ => RangeInclusive::new(0., f64::MAX),
+        }
+    }
+
+    // Computes the value of a metric for a space.
+    pub(crate) fn compute(
+        &self,
+        space: &FuncSpace,
+        syntax: &SpaceSyntax,
+        options: &MetricsOptions,
+    ) -> f64 {
+        let context = Context { syntax, options };
+        match self {
+            Self::Cyclomatic => Cyclomatic::value(space, &context),
+            Self::Cognitive => Cognitive::value(space, &context),
+            Self::HalsteadVolume => HalsteadVolume::value(space, &context),
+            Self::HalsteadDifficulty => HalsteadDifficulty::value(space, &context),
+            Self::HalsteadEffort => HalsteadEffort::value(space, &context),
+            Self::HalsteadBugs => HalsteadBugs::value(space, &context),
+            Self::MaintainabilityIndex => MaintainabilityIndex::value(space, &context),
+            Self::Sloc => Sloc::value(space, &context),
+            Self::Ploc => Ploc::value(space, &context),
+            Self::Lloc => Lloc::value(space, &context),
+            Self::Cloc => Cloc::value(space, &context),
+            Self::Blank => Blank::value(space, &context),
+            Self::CommentRatio => CommentRatio::value(space, &context),
+            Self::Nargs => Nargs::value(space, &context),
+            Self::Nexits => Nexits::value(space, &context),
+            Self::Nesting => Nesting::value(space, &context),
+            Self::Abc => Abc::value(space, &context),
+            Self::AbcAssignments => AbcAssignments::value(space, &context),
+            Self::AbcBranches => AbcBranches::value(space, &context),
+            Self::AbcConditions => AbcConditions::value(space, &context),
+            Self::Wmc => Wmc::value(space, &context),
+            Self::Nom => Nom::value(space, &context),
+            Self::Npm => Npm::value(space, &context),
+            Self::Npa => Npa::value(space, &context),
+            Self::Composite => CompositeScore::value(space, &context),
+        }
+    }
+
+    pub(crate) fn value(
+        &self,
+        space: &FuncSpace,
+        syntax: &SpaceSyntax,
+        threshold: f64,
+        options: &MetricsOptions,
+    ) -> Option<f64> {
+        let context = Context { syntax, options };
         match self {
-            Self::Cyclomatic => Cyclomatic::check(space, threshold),
-            Self::Cognitive => Cognitive::check(space, threshold),
+            Self::Cyclomatic => Cyclomatic::check(space, &context, threshold),
+            Self::Cognitive => Cognitive::check(space, &context, threshold),
+            Self::HalsteadVolume => HalsteadVolume::check(space, &context, threshold),
+            Self::HalsteadDifficulty => HalsteadDifficulty::check(space, &context, threshold),
+            Self::HalsteadEffort => HalsteadEffort::check(space, &context, threshold),
+            Self::HalsteadBugs => HalsteadBugs::check(space, &context, threshold),
+            Self::MaintainabilityIndex => MaintainabilityIndex::check(space, &context, threshold),
+            Self::Sloc => Sloc::check(space, &context, threshold),
+            Self::Ploc => Ploc::check(space, &context, threshold),
+            Self::Lloc => Lloc::check(space, &context, threshold),
+            Self::Cloc => Cloc::check(space, &context, threshold),
+            Self::Blank => Blank::check(space, &context, threshold),
+            Self::CommentRatio => CommentRatio::check(space, &context, threshold),
+            Self::Nargs => Nargs::check(space, &context, threshold),
+            Self::Nexits => Nexits::check(space, &context, threshold),
+            Self::Nesting => Nesting::check(space, &context, threshold),
+            Self::Abc => Abc::check(space, &context, threshold),
+            Self::AbcAssignments => AbcAssignments::check(space, &context, threshold),
+            Self::AbcBranches => AbcBranches::check(space, &context, threshold),
+            Self::AbcConditions => AbcConditions::check(space, &context, threshold),
+            Self::Wmc => Wmc::check(space, &context, threshold),
+            Self::Nom => Nom::check(space, &context, threshold),
+            Self::Npm => Npm::check(space, &context, threshold),
+            Self::Npa => Npa::check(space, &context, threshold),
+            Self::Composite => CompositeScore::check(space, &context, threshold),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rust_code_analysis::{get_function_spaces, LANG};
+
+    use super::*;
+    use crate::syntax::parse;
+
+    // Parses a source file, returning its unit space.
+    fn unit(language: LANG, source: &str) -> FuncSpace {
+        get_function_spaces(
+            &language,
+            source.as_bytes().to_vec(),
+            Path::new("test"),
+            None,
+        )
+        .unwrap()
+    }
+
+    const OPTIONS: MetricsOptions = MetricsOptions {
+        mi_variant: MiVariant::VisualStudio,
+        composite: None,
+    };
+
+    // Computes the value of a metric which does not depend on the syntax.
+    fn value<C: ComplexityChecker>(space: &FuncSpace) -> f64 {
+        let syntax = SpaceSyntax::default();
+        C::value(
+            space,
+            &Context {
+                syntax: &syntax,
+                options: &OPTIONS,
+            },
+        )
+    }
+
+    fn check<C: ComplexityChecker>(space: &FuncSpace, threshold: f64) -> Option<f64> {
+        let syntax = SpaceSyntax::default();
+        C::check(
+            space,
+            &Context {
+                syntax: &syntax,
+                options: &OPTIONS,
+            },
+            threshold,
+        )
+    }
+
+    // Finds a space by name, visiting the subspaces of a space.
+    fn find<'a>(space: &'a FuncSpace, name: &str) -> &'a FuncSpace {
+        fn visit<'a>(space: &'a FuncSpace, name: &str) -> Option<&'a FuncSpace> {
+            if space.name.as_deref() == Some(name) {
+                return Some(space);
+            }
+            space.spaces.iter().find_map(|space| visit(space, name))
+        }
+        visit(space, name).unwrap()
+    }
+
+    #[test]
+    fn comment_ratio() {
+        let unit = unit(
+            LANG::Rust,
+            "fn documented() {
+    // The first value.
+    // The second value.
+    let x = 1;
+    let y = 2;
+    let z = 3;
+    let w = 4;
+}
+
+fn undocumented() {
+    let x = 1;
+    let y = 2;
+    let z = 3;
+    let w = 4;
+}
+
+fn short() {
+    // A single value.
+    let x = 1;
+}
+
+fn empty() {}
+",
+        );
+        assert_eq!(value::<CommentRatio>(find(&unit, "documented")), 2. / 6.);
+        assert_eq!(value::<CommentRatio>(find(&unit, "undocumented")), 0.);
+        // Spaces with a few instruction lines have no comment ratio.
+        assert!(value::<CommentRatio>(find(&unit, "short")).is_nan());
+        assert!(value::<CommentRatio>(find(&unit, "empty")).is_nan());
+
+        // Lower ratios are worse, and subspaces are always visited.
+        let comment_ratio = |name| {
+            Complexity::CommentRatio.value(
+                find(&unit, name),
+                &SpaceSyntax::default(),
+                0.1,
+                &MetricsOptions {
+                    mi_variant: MiVariant::VisualStudio,
+                    composite: None,
+                },
+            )
+        };
+        assert_eq!(comment_ratio("documented"), Some(2. / 6.));
+        assert!(!Complexity::CommentRatio.exceeds(2. / 6., 0.1));
+        assert!(Complexity::CommentRatio.exceeds(0., 0.1));
+        // An undefined ratio never exceeds a threshold.
+        let short = comment_ratio("short").unwrap();
+        assert!(!Complexity::CommentRatio.exceeds(short, 0.1));
+    }
+
+    #[test]
+    fn maintainability_index() {
+        let unit = unit(
+            LANG::Rust,
+            "fn sign(x: i32) -> i32 {
+    if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+",
+        );
+        let function = find(&unit, "sign");
+        let mi = |mi_variant| {
+            Complexity::MaintainabilityIndex.compute(
+                function,
+                &SpaceSyntax::default(),
+                &MetricsOptions {
+                    mi_variant,
+                    composite: None,
+                },
+            )
+        };
+
+        // The value depends on the selected variant.
+        let mi_metrics = &function.metrics.mi;
+        assert_eq!(mi(MiVariant::Original), mi_metrics.mi_original());
+        assert_eq!(mi(MiVariant::Sei), mi_metrics.mi_sei());
+        assert_eq!(mi(MiVariant::VisualStudio), mi_metrics.mi_visual_studio());
+        for mi_variant in [MiVariant::Original, MiVariant::Sei, MiVariant::VisualStudio] {
+            assert!(mi_variant.range().contains(&mi(mi_variant)));
+        }
+
+        // Lower values are worse, and subspaces are always visited.
+        let visual_studio = mi(MiVariant::VisualStudio);
+        assert!(Complexity::MaintainabilityIndex.exceeds(visual_studio, visual_studio + 1.));
+        assert!(!Complexity::MaintainabilityIndex.exceeds(visual_studio, visual_studio - 1.));
+        assert_eq!(
+            check::<MaintainabilityIndex>(&unit, 0.),
+            Some(value::<MaintainabilityIndex>(&unit))
+        );
+    }
+
+    #[test]
+    fn halstead_checks() {
+        let unit = unit(
+            LANG::Rust,
+            "fn small(x: i32) -> i32 {
+    x + 1
+}
+
+fn large(x: i32, y: i32) -> i32 {
+    let z = x * y + x - y;
+    z * z + x / 2
+}
+",
+        );
+        let small = find(&unit, "small");
+        let large = find(&unit, "large");
+
+        // More operators and operands produce higher values.
+        assert!(value::<HalsteadVolume>(large) > value::<HalsteadVolume>(small));
+        assert!(value::<HalsteadEffort>(large) > value::<HalsteadEffort>(small));
+        assert!(value::<HalsteadBugs>(large) > value::<HalsteadBugs>(small));
+        // Values are not monotonic, so subspaces are always visited.
+        assert_eq!(
+            check::<HalsteadEffort>(&unit, f64::MAX),
+            Some(value::<HalsteadEffort>(&unit))
+        );
+    }
+
+    #[test]
+    fn lines_checks() {
+        let unit = unit(
+            LANG::Rust,
+            "fn outer() {
+    let x = 1;
+
+    let y = 2;
+}
+",
+        );
+        let outer = find(&unit, "outer");
+        assert_eq!(value::<Sloc>(outer), 5.);
+        assert_eq!(value::<Blank>(outer), 1.);
+        // Spaces below the threshold are not visited.
+        assert_eq!(check::<Sloc>(outer, 4.), Some(5.));
+        assert_eq!(check::<Sloc>(outer, 5.), None);
+    }
+
+    #[test]
+    fn arguments_and_exits() {
+        let unit = unit(
+            LANG::Rust,
+            "fn exits(a: i32, b: i32, c: i32) -> i32 {
+    if a > b {
+        return a;
+    }
+    let add = |x: i32, y: i32| {
+        if x > 0 {
+            return x;
+        }
+        x + y
+    };
+    if b > c {
+        return b;
+    }
+    add(b, c)
+}
+",
+        );
+        let function = find(&unit, "exits");
+        let closure = find(&unit, "<anonymous>");
+
+        // Closure arguments are counted separately from function arguments.
+        assert_eq!(value::<Nargs>(function), 3.);
+        assert_eq!(value::<Nargs>(closure), 2.);
+        assert_eq!(value::<Nexits>(function), 3.);
+        assert_eq!(value::<Nexits>(closure), 1.);
+
+        // A space is visited when one of its subspaces exceeds the threshold.
+        assert_eq!(check::<Nargs>(&unit, 2.), Some(0.));
+        assert_eq!(check::<Nargs>(function, 2.), Some(3.));
+        assert_eq!(check::<Nargs>(closure, 2.), None);
+        assert_eq!(check::<Nargs>(function, 3.), None);
+        assert_eq!(check::<Nexits>(&unit, 2.), Some(0.));
+        assert_eq!(check::<Nexits>(closure, 0.), Some(1.));
+        assert_eq!(check::<Nexits>(function, 3.), None);
+    }
+
+    #[test]
+    fn abc() {
+        let unit = unit(
+            LANG::Java,
+            "class Counter {
+    int count;
+
+    void add(int value) {
+        if (value > 0 && count < 100) {
+            count += value;
+        } else {
+            reset();
+        }
+    }
+
+    void reset() {
+        count = 0;
+    }
+}
+",
+        );
+        let class = find(&unit, "Counter");
+        let add = find(&unit, "add");
+        let reset = find(&unit, "reset");
+
+        assert_eq!(value::<AbcAssignments>(add), 1.);
+        assert_eq!(value::<AbcBranches>(add), 1.);
+        assert_eq!(value::<AbcConditions>(add), 3.);
+        assert_eq!(value::<Abc>(add), 11f64.sqrt());
+        assert_eq!(value::<AbcAssignments>(reset), 1.);
+        assert_eq!(value::<AbcBranches>(reset), 0.);
+        assert_eq!(value::<AbcConditions>(reset), 0.);
+        assert_eq!(value::<Abc>(reset), 1.);
+        // The values of a space are the sum of the values of its subspaces.
+        assert_eq!(value::<AbcAssignments>(class), 2.);
+        assert_eq!(value::<Abc>(class), 14f64.sqrt());
+
+        assert_eq!(check::<Abc>(add, 3.), Some(11f64.sqrt()));
+        assert_eq!(check::<Abc>(reset, 3.), None);
+        assert_eq!(check::<AbcAssignments>(class, 1.), Some(2.));
+        assert_eq!(check::<AbcAssignments>(add, 1.), None);
+        assert_eq!(check::<AbcBranches>(add, 0.), Some(1.));
+        assert_eq!(check::<AbcBranches>(reset, 0.), None);
+        assert_eq!(check::<AbcConditions>(add, 2.), Some(3.));
+        assert_eq!(check::<AbcConditions>(add, 3.), None);
+    }
+
+    #[test]
+    fn class_metrics() {
+        let options = MetricsOptions {
+            mi_variant: MiVariant::VisualStudio,
+            composite: None,
+        };
+        let class_metrics = |language, source| {
+            let unit = unit(language, source);
+            let (syntax, _) = parse(language, source);
+            let class = &unit.spaces[0];
+            let syntax = syntax.subspace(0, class);
+            let values: Vec<f64> = [
+                Complexity::Wmc,
+                Complexity::Nom,
+                Complexity::Npm,
+                Complexity::Npa,
+            ]
+            .iter()
+            .map(|complexity| complexity.compute(class, syntax, &options))
+            .collect();
+            // Methods breakdown of a class snippet.
+            let methods: Vec<(String, f64)> = methods(class)
+                .map(|method| {
+                    (
+                        method.name.clone().unwrap_or_default(),
+                        method.metrics.cyclomatic.cyclomatic(),
+                    )
+                })
+                .collect();
+            (values, methods)
+        };
+
+        // Members of Rust and C++ code are counted on the syntax tree.
+        let (values, methods) = class_metrics(
+            LANG::Rust,
+            "impl Point {
+    pub const ORIGIN: i32 = 0;
+
+    pub fn new(x: i32) -> i32 {
+        if x > 0 {
+            x
+        } else {
+            0
+        }
+    }
+
+    pub fn get(&self) -> i32 {
+        self.x
+    }
+
+    fn hidden(&self) {}
+}
+",
+        );
+        assert_eq!(values, [4., 3., 2., 1.]);
+        assert_eq!(
+            methods,
+            [
+                ("new".to_string(), 2.),
+                ("get".to_string(), 1.),
+                ("hidden".to_string(), 1.)
+            ]
+        );
+
+        let (values, methods) = class_metrics(
+            LANG::Cpp,
+            "class Shape {
+    int id;
+public:
+    int width;
+    int area() { return width; }
+    void resize(int w);
+};
+",
+        );
+        assert_eq!(values, [1., 2., 2., 1.]);
+        assert_eq!(methods, [("area".to_string(), 1.)]);
+
+        // Declared methods are counted, but they are not weighted.
+        let (values, methods) = class_metrics(
+            LANG::Cpp,
+            "class Shape {
+public:
+    Shape();
+    int area() const;
+private:
+    void resize(int w);
+};
+",
+        );
+        assert_eq!(values, [0., 3., 2., 0.]);
+        assert!(methods.is_empty());
+
+        // Public members of Java code are counted by rust-code-analysis.
+        let (values, methods) = class_metrics(
+            LANG::Java,
+            "class Counter {
+    public int count;
+    private int step;
+
+    public void add(int value) {
+        if (value > 0) {
+            count += value;
+        }
+    }
+
+    private void reset() {
+        count = 0;
+    }
+}
+",
+        );
+        assert_eq!(values, [3., 2., 1., 1.]);
+        assert_eq!(
+            methods,
+            [("add".to_string(), 2.), ("reset".to_string(), 1.)]
+        );
+    }
+}
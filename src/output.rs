@@ -3,8 +3,19 @@ use std::io::prelude::*;
 use std::path::{Path, PathBuf};
 
 use arg_enum_proc_macro::ArgEnum;
+use serde::Serialize;
 use tracing::debug;
 
+use crate::explain::IncrementData;
+use crate::report::{
+    Checkstyle, CodeClimate, JUnit, Offender, ReportConfig, Sarif, Summary, Tables, WriteReport,
+};
+use crate::snippets::{
+    ComponentData, ElisionData, FunctionData, MethodData, RelativeLinesData, SnippetData,
+    SpaceDetails, SuppressedData,
+};
+use crate::suppression::SuppressionData;
+use crate::syntax::NestingData;
 use crate::Snippets;
 use crate::{Error, Result};
 
@@ -20,6 +31,27 @@ pub enum OutputFormat {
     /// Json format.
     #[arg_enum(name = "json")]
     Json,
+    /// Sarif 2.1.0 format, a single log for all the analyzed files.
+    #[arg_enum(name = "sarif")]
+    Sarif,
+    /// Code Climate format, a single report for all the analyzed files
+    /// which is read by the GitLab Code Quality widget.
+    #[arg_enum(name = "codeclimate", alias = "gitlab")]
+    CodeClimate,
+    /// Checkstyle XML format, a single report for all the analyzed files.
+    #[arg_enum(name = "checkstyle")]
+    Checkstyle,
+    /// JUnit XML format, a single report for all the analyzed files where
+    /// each function is a test case, which fails when the function
+    /// exceeds some thresholds.
+    #[arg_enum(name = "junit")]
+    JUnit,
+    /// Csv format, a single table for all the analyzed files.
+    #[arg_enum(name = "csv")]
+    Csv,
+    /// Tsv format, a single table for all the analyzed files.
+    #[arg_enum(name = "tsv")]
+    Tsv,
     /// Enables all supported output formats.
     #[arg_enum(name = "all")]
     All,
@@ -31,25 +63,81 @@ impl OutputFormat {
         "markdown"
     }
 
+    // Whether the format reports every analyzed space, also when it does
+    // not exceed any threshold, at the end of the analysis.
+    pub(crate) fn reports_spaces(&self) -> bool {
+        matches!(self, Self::JUnit | Self::All)
+    }
+
+    // Creates the tables of the format, if any, which are streamed while
+    // the files are analyzed.
+    pub(crate) fn create_tables<P: AsRef<Path>>(
+        &self,
+        output_path: P,
+        all_functions: bool,
+    ) -> Result<Option<Tables>> {
+        let (csv, tsv) = match self {
+            Self::Csv => (true, false),
+            Self::Tsv => (false, true),
+            Self::All => (true, true),
+            _ => return Ok(None),
+        };
+        Tables::create(output_path.as_ref(), csv, tsv, all_functions).map(Some)
+    }
+
     pub(crate) fn write_format<P: AsRef<Path>>(
         &self,
         output_path: P,
         snippets: &[Snippets],
+        config: &ReportConfig,
     ) -> Result<()> {
         // Create output filenames.
         let filenames = create_filenames(snippets);
 
         let output_path = output_path.as_ref();
 
+        // Markdown, html and json outputs can be a single report.
+        let markdown = || {
+            if config.single_file {
+                MarkdownReport::write_format(output_path, snippets, config)
+            } else {
+                Markdown::write_format(output_path, &filenames, snippets)
+            }
+        };
+        let html = || {
+            if config.single_file {
+                HtmlReport::write_format(output_path, snippets, config)
+            } else {
+                Html::write_format(output_path, &filenames, snippets)
+            }
+        };
+        let json = || {
+            if config.single_file {
+                JsonReport::write_format(output_path, snippets, config)
+            } else {
+                Json::write_format(output_path, &filenames, snippets)
+            }
+        };
+
         match self {
             Self::All => {
-                Markdown::write_format(output_path, &filenames, snippets)?;
-                Html::write_format(output_path, &filenames, snippets)?;
-                Json::write_format(output_path, &filenames, snippets)
+                markdown()?;
+                html()?;
+                json()?;
+                Sarif::write_format(output_path, snippets, config)?;
+                CodeClimate::write_format(output_path, snippets, config)?;
+                Checkstyle::write_format(output_path, snippets, config)?;
+                JUnit::write_format(output_path, snippets, config)
             }
-            Self::Json => Json::write_format(output_path, &filenames, snippets),
-            Self::Markdown => Markdown::write_format(output_path, &filenames, snippets),
-            Self::Html => Html::write_format(output_path, &filenames, snippets),
+            Self::Json => json(),
+            Self::Sarif => Sarif::write_format(output_path, snippets, config),
+            Self::CodeClimate => CodeClimate::write_format(output_path, snippets, config),
+            Self::Checkstyle => Checkstyle::write_format(output_path, snippets, config),
+            Self::JUnit => JUnit::write_format(output_path, snippets, config),
+            // Tables are streamed while the files are analyzed.
+            Self::Csv | Self::Tsv => Ok(()),
+            Self::Markdown => markdown(),
+            Self::Html => html(),
         }
     }
 }
@@ -71,6 +159,7 @@ fn create_filenames(snippets: &[Snippets]) -> Vec<String> {
         .collect()
 }
 
+// Formats which write a file for each source file containing complex code.
 trait WriteFormat {
     const EXTENSION: &'static str;
     const DIR: &'static str;
@@ -94,6 +183,82 @@ trait WriteFormat {
     }
 }
 
+// Text of a markdown table cell, escaped so it does not break the row.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+// Metrics suppressed by a suppression comment.
+fn suppressed_metrics(suppression: &SuppressionData) -> String {
+    if suppression.metrics.is_empty() {
+        "all".to_string()
+    } else {
+        suppression
+            .metrics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+// Text of a snippet, or of a function, together with its context.
+struct Code<'a> {
+    text: &'a str,
+    start_line: usize,
+    leading_context: &'a str,
+    trailing_context: &'a str,
+    elided: Option<ElisionData>,
+    explanation: &'a [IncrementData],
+}
+
+impl<'a> Code<'a> {
+    fn new<T>(start_line: usize, details: &'a SpaceDetails<T>) -> Self {
+        Self {
+            text: &details.text,
+            start_line,
+            leading_context: &details.leading_context,
+            trailing_context: &details.trailing_context,
+            elided: details.elided,
+            explanation: &details.explanation,
+        }
+    }
+}
+
+impl<'a> Code<'a> {
+    // Lines of the text, each one with the annotation of the increments
+    // caused by the line, if any.
+    fn lines(&self) -> impl Iterator<Item = (&'a str, Option<String>)> + '_ {
+        self.text.lines().enumerate().map(|(index, line)| {
+            let mut number = self.start_line + index;
+            if let Some(elided) = self.elided {
+                // The line which replaces the elided lines is not annotated.
+                if number == elided.start_line {
+                    return (line, None);
+                }
+                if number > elided.start_line {
+                    number += elided.end_line - elided.start_line;
+                }
+            }
+            let annotation = self
+                .explanation
+                .iter()
+                .filter(|increment| increment.line == number)
+                .map(|increment| {
+                    format!(
+                        "{} +{}: {}",
+                        increment.metric, increment.increment, increment.reason
+                    )
+                })
+                .collect::<Vec<String>>();
+            (
+                line,
+                (!annotation.is_empty()).then(|| annotation.join("; ")),
+            )
+        })
+    }
+}
+
 struct Markdown;
 
 impl WriteFormat for Markdown {
@@ -105,39 +270,315 @@ impl WriteFormat for Markdown {
 
         for (filename, snippet) in filenames.iter().zip(snippets) {
             let mut markdown_file = Self::create_file(&dir.join(filename), Self::EXTENSION)?;
+            write!(markdown_file, "{}", Self::file(snippet, 1))?;
+        }
+        Ok(())
+    }
+}
+
+impl Markdown {
+    // Sections of a source file, whose headings start from a level.
+    fn file(snippet: &Snippets, level: usize) -> String {
+        let heading = "#".repeat(level);
+        let language = snippet.language.name();
+        let mut sections = Vec::new();
 
-            for (complexity_name, all_snippets) in snippet.snippets.iter() {
-                writeln!(
-                    markdown_file,
-                    r#"# {complexity_name}
+        for (complexity_name, all_snippets) in snippet.snippets.iter() {
+            sections.push(format!(
+                r#"{heading} {complexity_name}
                 {snippets}"#,
-                    snippets = all_snippets
-                        .iter()
-                        .map(|v| {
-                            format!(
-                                r#"
+                snippets = Self::snippets(all_snippets, language)
+            ));
+        }
+
+        if !snippet.functions.is_empty() {
+            sections.push(format!(
+                "{heading} Functions\n{functions}",
+                functions = Self::functions(&snippet.functions, language, level)
+            ));
+        }
+
+        if !snippet.suppressed.is_empty() {
+            sections.push(format!(
+                "{heading} Suppressed\n{suppressed}",
+                suppressed = Self::suppressed(&snippet.suppressed)
+            ));
+        }
+
+        if !snippet.unused_suppressions.is_empty() {
+            sections.push(format!(
+                "{heading} Unused suppressions\n{unused}",
+                unused = Self::unused_suppressions(&snippet.unused_suppressions)
+            ));
+        }
+
+        sections.into_iter().map(|section| section + "\n").collect()
+    }
+
+    fn snippets(snippets: &[SnippetData], language: &str) -> String {
+        snippets
+            .iter()
+            .map(|v| {
+                format!(
+                    r#"
+*space:* **{qualified_name}**
+
+*kind:* **{kind}**
+
 *complexity:* **{complexity}**
 
-*start line:* **{start_line}**
+*start line:* **{start_line}**, *column:* **{start_column}**
+
+*end line:* **{end_line}**, *column:* **{end_column}**
+
+*fingerprint:* `{fingerprint}`
+{relative_lines}{nesting}{components}{methods}{code}{children}"#,
+                    qualified_name = v.location.qualified_name,
+                    kind = v.location.kind,
+                    complexity = v.complexity,
+                    start_line = v.location.start_line,
+                    end_line = v.location.end_line,
+                    start_column = v.location.start_column,
+                    end_column = v.location.end_column,
+                    fingerprint = v.location.fingerprint,
+                    relative_lines = Self::relative_lines(v.details.relative_lines),
+                    nesting = Self::nesting(v.details.nesting),
+                    components = Self::components(&v.details.components),
+                    methods = Self::methods(&v.details.methods),
+                    code = Self::code(&Code::new(v.location.start_line, &v.details), language),
+                    children =
+                        Self::children("snippets", Self::snippets(&v.details.children, language)),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    fn functions(functions: &[FunctionData], language: &str, level: usize) -> String {
+        functions
+            .iter()
+            .map(|f| {
+                format!(
+                    r#"
+{heading}# {qualified_name}
 
-*end line:* **{end_line}**
+*kind:* **{kind}**
 
-```{language}
-{text}
-```"#,
-                                complexity = v.complexity,
-                                start_line = v.start_line,
-                                end_line = v.end_line,
-                                language = snippet.language.name(),
-                                text = v.text
-                            )
-                        })
+*start line:* **{start_line}**, *column:* **{start_column}**
+
+*end line:* **{end_line}**, *column:* **{end_column}**
+
+*fingerprint:* `{fingerprint}`
+{relative_lines}
+| metric | value | exceeded |
+|--------|-------|----------|
+{metrics}
+{nesting}{components}{methods}{code}{children}"#,
+                    heading = "#".repeat(level),
+                    qualified_name = f.location.qualified_name,
+                    kind = f.location.kind,
+                    start_line = f.location.start_line,
+                    end_line = f.location.end_line,
+                    start_column = f.location.start_column,
+                    end_column = f.location.end_column,
+                    fingerprint = f.location.fingerprint,
+                    relative_lines = Self::relative_lines(f.details.relative_lines),
+                    metrics = f
+                        .metrics
+                        .iter()
+                        .map(|(metric, value)| format!(
+                            "| {metric} | {value} | {} |",
+                            if f.exceeded.contains(metric) {
+                                "**yes**"
+                            } else {
+                                "no"
+                            }
+                        ))
                         .collect::<Vec<String>>()
-                        .join("\n\n")
-                )?;
+                        .join("\n"),
+                    nesting = Self::nesting(f.details.nesting),
+                    components = Self::components(&f.details.components),
+                    methods = Self::methods(&f.details.methods),
+                    code = Self::code(&Code::new(f.location.start_line, &f.details), language),
+                    children = Self::children(
+                        "functions",
+                        Self::functions(&f.details.children, language, level)
+                    ),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    fn suppressed(suppressed: &[SuppressedData]) -> String {
+        format!(
+            r#"
+| space | metric | complexity | start line | end line | suppression line | reason |
+|-------|--------|------------|------------|----------|------------------|--------|
+{rows}
+"#,
+            rows = suppressed
+                .iter()
+                .map(|s| format!(
+                    "| {} | {} | {} | {} | {} | {} | {} |",
+                    cell(&s.location.qualified_name),
+                    s.metric,
+                    s.complexity,
+                    s.location.start_line,
+                    s.location.end_line,
+                    s.suppression.line,
+                    cell(s.suppression.reason.as_deref().unwrap_or_default())
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    fn unused_suppressions(suppressions: &[SuppressionData]) -> String {
+        format!(
+            r#"
+| line | metrics | reason |
+|------|---------|--------|
+{rows}
+"#,
+            rows = suppressions
+                .iter()
+                .map(|s| format!(
+                    "| {} | {} | {} |",
+                    s.line,
+                    suppressed_metrics(s),
+                    cell(s.reason.as_deref().unwrap_or_default())
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    fn nesting(nesting: Option<NestingData>) -> String {
+        nesting.map_or_else(String::new, |nesting| {
+            format!("\n*deepest line:* **{}**\n", nesting.line)
+        })
+    }
+
+    fn relative_lines(relative_lines: Option<RelativeLinesData>) -> String {
+        relative_lines.map_or_else(String::new, |lines| {
+            format!(
+                "\n*relative lines:* **{}-{}**\n",
+                lines.start_line, lines.end_line
+            )
+        })
+    }
+
+    // Nested snippets are quoted below their parent.
+    fn children(name: &str, children: String) -> String {
+        if children.is_empty() {
+            return String::new();
+        }
+        format!(
+            "\n*nested {name}:*\n{}\n",
+            children
+                .lines()
+                .map(|line| if line.is_empty() {
+                    ">".to_string()
+                } else {
+                    format!("> {line}")
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    fn code(code: &Code, language: &str) -> String {
+        if code.text.is_empty() {
+            return Self::explanation(code.explanation);
+        }
+        let context = |name: &str, context: &str| {
+            if context.is_empty() {
+                String::new()
+            } else {
+                format!("\n*{name} context:*\n\n```{language}\n{context}\n```\n")
             }
+        };
+        // Annotations are comments of the snippet language.
+        let comment = if language == "python" { "#" } else { "//" };
+        format!(
+            "{elided}{leading}\n```{language}\n{text}\n```\n{trailing}",
+            elided = code.elided.map_or_else(String::new, |elided| format!(
+                "\n*elided lines:* **{}-{}**\n",
+                elided.start_line, elided.end_line
+            )),
+            leading = context("leading", code.leading_context),
+            text = code
+                .lines()
+                .map(|(line, annotation)| match annotation {
+                    Some(annotation) => format!("{line}  {comment} {annotation}"),
+                    None => line.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            trailing = context("trailing", code.trailing_context),
+        )
+    }
+
+    fn explanation(explanation: &[IncrementData]) -> String {
+        if explanation.is_empty() {
+            return String::new();
         }
-        Ok(())
+        format!(
+            "\n*explanation:*\n\n{items}\n",
+            items = explanation
+                .iter()
+                .map(|i| format!(
+                    "- line {}: {} **+{}**, {}",
+                    i.line, i.metric, i.increment, i.reason
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    fn components(components: &[ComponentData]) -> String {
+        if components.is_empty() {
+            return String::new();
+        }
+        format!(
+            r#"
+*components:*
+
+{items}
+"#,
+            items = components
+                .iter()
+                .map(|c| format!("- {}: **{}**", c.metric, c.value))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    fn methods(methods: &[MethodData]) -> String {
+        if methods.is_empty() {
+            return String::new();
+        }
+        format!(
+            r#"
+*methods:*
+
+| name | cyclomatic | start line | end line |
+|------|------------|------------|----------|
+{rows}
+"#,
+            rows = methods
+                .iter()
+                .map(|m| format!(
+                    "| {} | {} | {} | {} |",
+                    cell(&m.name),
+                    m.cyclomatic,
+                    m.start_line,
+                    m.end_line
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
     }
 }
 
@@ -176,36 +617,7 @@ impl WriteFormat for Html {
             let title = path
                 .file_name()
                 .map_or("Unknown file", |os| os.to_str().unwrap_or("Unknown file"));
-            let body = snippet
-                .snippets
-                .iter()
-                .map(|(complexity_name, all_snippets)| {
-                    format!(
-                        r#"<h1>{complexity_name}</h1>{snippet}"#,
-                        snippet = all_snippets
-                            .iter()
-                            .map(|v| {
-                                format!(
-                                    r#"
-<p>
-    complexity: <b>{complexity}</b><br>
-    start line: <b>{start_line}</b><br>
-    end line: <b>{end_line}</b><br>
-    <pre><code>{text}
-    </code></pre>
-</p>"#,
-                                    complexity = v.complexity,
-                                    start_line = v.start_line,
-                                    end_line = v.end_line,
-                                    text = html_escape::encode_text(&v.text),
-                                )
-                            })
-                            .collect::<Vec<String>>()
-                            .join("\n\n")
-                    )
-                })
-                .collect::<Vec<String>>()
-                .join("\n\n");
+            let body = Self::file(snippet, 1);
             writeln!(
                 html_file,
                 r#"<!DOCTYPE html>
@@ -220,7 +632,7 @@ impl WriteFormat for Html {
             )?;
         }
 
-        let mut index_file = File::create(&dir.join("index.html"))?;
+        let mut index_file = File::create(dir.join("index.html"))?;
         writeln!(
             index_file,
             r#"<!DOCTYPE html>
@@ -238,6 +650,294 @@ impl WriteFormat for Html {
     }
 }
 
+impl Html {
+    // Sections of a source file, whose headings start from a level.
+    fn file(snippet: &Snippets, level: usize) -> String {
+        snippet
+            .snippets
+            .iter()
+            .map(|(complexity_name, all_snippets)| {
+                format!(
+                    r#"<h{level}>{complexity_name}</h{level}>{snippet}"#,
+                    snippet = Self::snippets(all_snippets)
+                )
+            })
+            .chain(
+                (!snippet.functions.is_empty()).then(|| Self::functions(&snippet.functions, level)),
+            )
+            .chain(
+                (!snippet.suppressed.is_empty())
+                    .then(|| Self::suppressed(&snippet.suppressed, level)),
+            )
+            .chain(
+                (!snippet.unused_suppressions.is_empty())
+                    .then(|| Self::unused_suppressions(&snippet.unused_suppressions, level)),
+            )
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    fn snippets(snippets: &[SnippetData]) -> String {
+        snippets
+            .iter()
+            .map(|v| {
+                format!(
+                    r#"
+<p>
+    space: <b>{qualified_name}</b><br>
+    kind: <b>{kind}</b><br>
+    complexity: <b>{complexity}</b><br>
+    start line: <b>{start_line}</b>, column: <b>{start_column}</b><br>
+    end line: <b>{end_line}</b>, column: <b>{end_column}</b><br>
+    fingerprint: <code>{fingerprint}</code><br>{relative_lines}{nesting}{components}{methods}{code}
+</p>{children}"#,
+                    qualified_name = html_escape::encode_text(&v.location.qualified_name),
+                    kind = v.location.kind,
+                    complexity = v.complexity,
+                    start_line = v.location.start_line,
+                    end_line = v.location.end_line,
+                    start_column = v.location.start_column,
+                    end_column = v.location.end_column,
+                    fingerprint = v.location.fingerprint,
+                    relative_lines = Self::relative_lines(v.details.relative_lines),
+                    nesting = Self::nesting(v.details.nesting),
+                    components = Self::components(&v.details.components),
+                    methods = Self::methods(&v.details.methods),
+                    code = Self::code(&Code::new(v.location.start_line, &v.details)),
+                    children = Self::children(Self::snippets(&v.details.children)),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    fn functions(functions: &[FunctionData], level: usize) -> String {
+        format!(
+            r#"<h{level}>Functions</h{level}>{functions}"#,
+            functions = Self::function_list(functions, level + 1)
+        )
+    }
+
+    fn function_list(functions: &[FunctionData], level: usize) -> String {
+        functions
+            .iter()
+            .map(|f| {
+                format!(
+                    r#"
+<h{level}>{qualified_name}</h{level}>
+<p>
+    kind: <b>{kind}</b><br>
+    start line: <b>{start_line}</b>, column: <b>{start_column}</b><br>
+    end line: <b>{end_line}</b>, column: <b>{end_column}</b><br>
+    fingerprint: <code>{fingerprint}</code><br>{relative_lines}
+    <table>
+        <tr><th>metric</th><th>value</th><th>exceeded</th></tr>
+        {metrics}
+    </table>{nesting}{components}{methods}{code}
+</p>{children}"#,
+                    qualified_name = html_escape::encode_text(&f.location.qualified_name),
+                    kind = f.location.kind,
+                    start_line = f.location.start_line,
+                    end_line = f.location.end_line,
+                    start_column = f.location.start_column,
+                    end_column = f.location.end_column,
+                    fingerprint = f.location.fingerprint,
+                    relative_lines = Self::relative_lines(f.details.relative_lines),
+                    metrics = f
+                        .metrics
+                        .iter()
+                        .map(|(metric, value)| format!(
+                            "<tr><td>{metric}</td><td>{value}</td><td>{}</td></tr>",
+                            if f.exceeded.contains(metric) {
+                                "<b>yes</b>"
+                            } else {
+                                "no"
+                            }
+                        ))
+                        .collect::<Vec<String>>()
+                        .join("\n        "),
+                    nesting = Self::nesting(f.details.nesting),
+                    components = Self::components(&f.details.components),
+                    methods = Self::methods(&f.details.methods),
+                    code = Self::code(&Code::new(f.location.start_line, &f.details)),
+                    children = Self::children(Self::function_list(&f.details.children, level)),
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    fn suppressed(suppressed: &[SuppressedData], level: usize) -> String {
+        format!(
+            r#"<h{level}>Suppressed</h{level}>
+<table>
+    <tr><th>space</th><th>metric</th><th>complexity</th><th>start line</th><th>end line</th><th>suppression line</th><th>reason</th></tr>
+    {rows}
+</table>"#,
+            rows = suppressed
+                .iter()
+                .map(|s| format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    html_escape::encode_text(&s.location.qualified_name),
+                    s.metric,
+                    s.complexity,
+                    s.location.start_line,
+                    s.location.end_line,
+                    s.suppression.line,
+                    html_escape::encode_text(s.suppression.reason.as_deref().unwrap_or_default())
+                ))
+                .collect::<Vec<String>>()
+                .join("\n    ")
+        )
+    }
+
+    fn unused_suppressions(suppressions: &[SuppressionData], level: usize) -> String {
+        format!(
+            r#"<h{level}>Unused suppressions</h{level}>
+<table>
+    <tr><th>line</th><th>metrics</th><th>reason</th></tr>
+    {rows}
+</table>"#,
+            rows = suppressions
+                .iter()
+                .map(|s| format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    s.line,
+                    suppressed_metrics(s),
+                    html_escape::encode_text(s.reason.as_deref().unwrap_or_default())
+                ))
+                .collect::<Vec<String>>()
+                .join("\n    ")
+        )
+    }
+
+    fn nesting(nesting: Option<NestingData>) -> String {
+        nesting.map_or_else(String::new, |nesting| {
+            format!("\n    deepest line: <b>{}</b><br>", nesting.line)
+        })
+    }
+
+    fn relative_lines(relative_lines: Option<RelativeLinesData>) -> String {
+        relative_lines.map_or_else(String::new, |lines| {
+            format!(
+                "\n    relative lines: <b>{}-{}</b><br>",
+                lines.start_line, lines.end_line
+            )
+        })
+    }
+
+    // Nested snippets are quoted below their parent.
+    fn children(children: String) -> String {
+        if children.is_empty() {
+            return String::new();
+        }
+        format!("\n<blockquote>{children}\n</blockquote>")
+    }
+
+    fn code(code: &Code) -> String {
+        if code.text.is_empty() {
+            return Self::explanation(code.explanation);
+        }
+        let context = |name: &str, context: &str| {
+            if context.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\n    {name} context:\n    <pre class=\"context\"><code>{}\n    </code></pre>",
+                    html_escape::encode_text(context)
+                )
+            }
+        };
+        format!(
+            "{elided}{leading}\n    <pre><code>{text}\n    </code></pre>{trailing}",
+            elided = code.elided.map_or_else(String::new, |elided| format!(
+                "\n    elided lines: <b>{}-{}</b><br>",
+                elided.start_line, elided.end_line
+            )),
+            leading = context("leading", code.leading_context),
+            text = code
+                .lines()
+                .map(|(line, annotation)| match annotation {
+                    Some(annotation) => format!(
+                        "<mark>{}</mark>  <span class=\"annotation\">{}</span>",
+                        html_escape::encode_text(line),
+                        html_escape::encode_text(&annotation)
+                    ),
+                    None => html_escape::encode_text(line).into_owned(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            trailing = context("trailing", code.trailing_context),
+        )
+    }
+
+    fn explanation(explanation: &[IncrementData]) -> String {
+        if explanation.is_empty() {
+            return String::new();
+        }
+        format!(
+            r#"
+    explanation:
+    <ul>
+        {items}
+    </ul>"#,
+            items = explanation
+                .iter()
+                .map(|i| format!(
+                    "<li>line {}: {} <b>+{}</b>, {}</li>",
+                    i.line,
+                    i.metric,
+                    i.increment,
+                    html_escape::encode_text(&i.reason)
+                ))
+                .collect::<Vec<String>>()
+                .join("\n        ")
+        )
+    }
+
+    fn components(components: &[ComponentData]) -> String {
+        if components.is_empty() {
+            return String::new();
+        }
+        format!(
+            r#"
+    components:
+    <ul>
+        {items}
+    </ul>"#,
+            items = components
+                .iter()
+                .map(|c| format!("<li>{}: <b>{}</b></li>", c.metric, c.value))
+                .collect::<Vec<String>>()
+                .join("\n        ")
+        )
+    }
+
+    fn methods(methods: &[MethodData]) -> String {
+        if methods.is_empty() {
+            return String::new();
+        }
+        format!(
+            r#"
+    <table>
+        <tr><th>name</th><th>cyclomatic</th><th>start line</th><th>end line</th></tr>
+        {rows}
+    </table>"#,
+            rows = methods
+                .iter()
+                .map(|m| format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    html_escape::encode_text(&m.name),
+                    m.cyclomatic,
+                    m.start_line,
+                    m.end_line
+                ))
+                .collect::<Vec<String>>()
+                .join("\n        ")
+        )
+    }
+}
+
 struct Json;
 
 impl WriteFormat for Json {
@@ -255,3 +955,247 @@ impl WriteFormat for Json {
         Ok(())
     }
 }
+
+// Source files containing complex code, sorted by path.
+fn complex_files(snippets: &[Snippets]) -> Vec<&Snippets> {
+    let mut files = snippets.iter().collect::<Vec<_>>();
+    files.sort_by(|a, b| a.source_path.cmp(&b.source_path));
+    files
+}
+
+// Threshold of an offender, empty when unknown.
+fn offender_threshold(offender: &Offender) -> String {
+    offender
+        .threshold
+        .map_or_else(String::new, |threshold| threshold.to_string())
+}
+
+struct MarkdownReport;
+
+impl WriteReport for MarkdownReport {
+    const FILENAME: &'static str = "complex-code-spotter.md";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let files = complex_files(snippets);
+        let summary = Summary::new(&files, config);
+
+        writeln!(
+            writer,
+            r#"# Summary
+
+*files:* **{files}**
+
+| metric | threshold | snippets | files |
+|--------|-----------|----------|-------|
+{metrics}
+
+## Files
+
+| file | language | snippets | suppressed |
+|------|----------|----------|------------|
+{file_rows}
+
+## Worst offenders
+
+| space | file | lines | metric | value | threshold |
+|-------|------|-------|--------|-------|-----------|
+{offenders}
+"#,
+            files = summary.files.len(),
+            metrics = summary
+                .metrics
+                .iter()
+                .map(|m| format!(
+                    "| {} | {} | {} | {} |",
+                    m.metric, m.threshold, m.snippets, m.files
+                ))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            file_rows = summary
+                .files
+                .iter()
+                .map(|f| format!(
+                    "| {} | {} | {} | {} |",
+                    cell(&f.path),
+                    f.language,
+                    f.snippets,
+                    f.suppressed
+                ))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            offenders = summary
+                .worst_offenders
+                .iter()
+                .map(|o| format!(
+                    "| {} | {} | {}-{} | {} | {} | {} |",
+                    cell(o.qualified_name),
+                    cell(&o.path),
+                    o.start_line,
+                    o.end_line,
+                    o.metric,
+                    o.value,
+                    offender_threshold(o)
+                ))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        )?;
+
+        for (file, summary) in files.iter().zip(&summary.files) {
+            write!(
+                writer,
+                "# {path}\n\n{sections}",
+                path = summary.path,
+                sections = Markdown::file(file, 2)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct HtmlReport;
+
+impl WriteReport for HtmlReport {
+    const FILENAME: &'static str = "complex-code-spotter.html";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let files = complex_files(snippets);
+        let summary = Summary::new(&files, config);
+
+        // Each file of the summary links to its section.
+        let sections = files
+            .iter()
+            .zip(&summary.files)
+            .enumerate()
+            .map(|(index, (file, summary))| {
+                format!(
+                    r#"<h1 id="file-{index}">{path}</h1>
+{sections}"#,
+                    path = html_escape::encode_text(&summary.path),
+                    sections = Html::file(file, 2)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n");
+
+        writeln!(
+            writer,
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Complex code report</title>
+</head>
+<body>
+    <h1>Summary</h1>
+    <p>files: <b>{files}</b></p>
+    <table>
+        <tr><th>metric</th><th>threshold</th><th>snippets</th><th>files</th></tr>
+        {metrics}
+    </table>
+    <h2>Files</h2>
+    <table>
+        <tr><th>file</th><th>language</th><th>snippets</th><th>suppressed</th></tr>
+        {file_rows}
+    </table>
+    <h2>Worst offenders</h2>
+    <table>
+        <tr><th>space</th><th>file</th><th>lines</th><th>metric</th><th>value</th><th>threshold</th></tr>
+        {offenders}
+    </table>
+    {sections}
+</body>
+</html>"#,
+            files = summary.files.len(),
+            metrics = summary
+                .metrics
+                .iter()
+                .map(|m| format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    m.metric, m.threshold, m.snippets, m.files
+                ))
+                .collect::<Vec<String>>()
+                .join("\n        "),
+            file_rows = summary
+                .files
+                .iter()
+                .enumerate()
+                .map(|(index, f)| format!(
+                    "<tr><td><a href=\"#file-{index}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    html_escape::encode_text(&f.path),
+                    f.language,
+                    f.snippets,
+                    f.suppressed
+                ))
+                .collect::<Vec<String>>()
+                .join("\n        "),
+            offenders = summary
+                .worst_offenders
+                .iter()
+                .map(|o| format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}-{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    html_escape::encode_text(o.qualified_name),
+                    html_escape::encode_text(&o.path),
+                    o.start_line,
+                    o.end_line,
+                    o.metric,
+                    o.value,
+                    offender_threshold(o)
+                ))
+                .collect::<Vec<String>>()
+                .join("\n        "),
+        )?;
+        Ok(())
+    }
+}
+
+// Single json report, containing a summary and the snippets of each file.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    summary: Summary<'a>,
+    files: Vec<&'a Snippets>,
+}
+
+impl WriteReport for JsonReport<'_> {
+    const FILENAME: &'static str = "complex-code-spotter.json";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let files = complex_files(snippets);
+        let report = JsonReport {
+            summary: Summary::new(&files, config),
+            files,
+        };
+        serde_json::to_writer_pretty(writer, &report)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_cells() {
+        let suppression =
+            SuppressionData::parse("// ccs:allow(cognitive, reason = \"lexer | parser\")", 3)
+                .unwrap();
+        let table = Markdown::unused_suppressions(&[suppression]);
+        let rows = table.trim().lines().collect::<Vec<_>>();
+
+        // Pipes are escaped, so each row has the same cells as the header.
+        let cells = |row: &str| row.replace("\\|", "").matches('|').count();
+        assert!(rows.iter().all(|row| cells(row) == cells(rows[0])));
+        assert_eq!(rows[2], "| 3 | Cognitive | lexer \\| parser |");
+        assert_eq!(cell("a\nb"), "a b");
+    }
+}
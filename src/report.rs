@@ -0,0 +1,1378 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::metrics::Complexity;
+use crate::snippets::{
+    relative_path, serialize_optional_value, serialize_value, FileSpaces, FunctionData,
+    SnippetData, SpaceData, SuppressedData,
+};
+use crate::space::SpaceKind;
+use crate::suppression::SuppressionData;
+use crate::Result;
+use crate::Snippets;
+
+// A metric of a space which exceeds its threshold.
+pub(crate) struct Finding<'a> {
+    pub(crate) source_path: &'a Path,
+    // Directory which the reported path is relative to.
+    pub(crate) root: &'a Path,
+    pub(crate) metric: Complexity,
+    pub(crate) value: f64,
+    // Threshold exceeded by the value, unknown when the metric has not
+    // been analyzed.
+    pub(crate) threshold: Option<f64>,
+    pub(crate) qualified_name: &'a str,
+    pub(crate) kind: SpaceKind,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+    // Columns start from 1, and they are 0 when unknown.
+    pub(crate) start_column: usize,
+    pub(crate) end_column: usize,
+    pub(crate) start_byte: usize,
+    pub(crate) end_byte: usize,
+    pub(crate) fingerprint: &'a str,
+    // Comment which suppresses the finding, if any.
+    pub(crate) suppression: Option<&'a SuppressionData>,
+}
+
+// Message describing the value of a metric for a space, together with the
+// threshold it exceeds, if known.
+fn message(metric: Complexity, qualified_name: &str, value: f64, threshold: Option<f64>) -> String {
+    let message = format!("{} of `{qualified_name}` is {value}", metric.description());
+    match threshold {
+        Some(threshold) if metric.lower_is_worse() => {
+            format!("{message}, below the threshold of {threshold}")
+        }
+        Some(threshold) => format!("{message}, above the threshold of {threshold}"),
+        None => message,
+    }
+}
+
+impl<'a> Finding<'a> {
+    // Path of the source file relative to the root, with forward slashes
+    // on every platform.
+    pub(crate) fn path(&self) -> String {
+        relative_path(self.source_path, self.root)
+    }
+
+    // Describes how the value exceeds the threshold.
+    pub(crate) fn message(&self) -> String {
+        message(self.metric, self.qualified_name, self.value, self.threshold)
+    }
+
+    // How far the value exceeds the threshold, relative to the threshold.
+    // It is unknown when the threshold is unknown or zero.
+    pub(crate) fn excess(&self) -> Option<f64> {
+        let threshold = self.threshold.filter(|threshold| *threshold != 0.)?;
+        let excess = if self.metric.lower_is_worse() {
+            threshold - self.value
+        } else {
+            self.value - threshold
+        };
+        Some(excess / threshold.abs())
+    }
+}
+
+// Collects the findings of some snippets, sorted by path, line and metric.
+pub(crate) fn findings<'a>(
+    snippets: &'a [Snippets],
+    config: &ReportConfig<'a>,
+) -> Vec<Finding<'a>> {
+    let thresholds: HashMap<Complexity, f64> = config.thresholds.iter().copied().collect();
+    let mut findings = Vec::new();
+    for snippets_data in snippets {
+        let source_path = snippets_data.source_path.as_path();
+        let finding = |metric: Complexity, value: f64| Finding {
+            source_path,
+            root: config.root,
+            metric,
+            value,
+            threshold: thresholds.get(&metric).copied(),
+            qualified_name: "",
+            kind: SpaceKind::Unit,
+            start_line: 0,
+            end_line: 0,
+            start_column: 0,
+            end_column: 0,
+            start_byte: 0,
+            end_byte: 0,
+            fingerprint: "",
+            suppression: None,
+        };
+        for (metric, snippets) in &snippets_data.snippets {
+            for_each_snippet(snippets, &mut |snippet| {
+                findings.push(Finding {
+                    qualified_name: &snippet.location.qualified_name,
+                    kind: snippet.location.kind,
+                    start_line: snippet.location.start_line,
+                    end_line: snippet.location.end_line,
+                    start_column: snippet.location.start_column,
+                    end_column: snippet.location.end_column,
+                    start_byte: snippet.location.start_byte,
+                    end_byte: snippet.location.end_byte,
+                    fingerprint: &snippet.location.fingerprint,
+                    ..finding(*metric, snippet.complexity)
+                });
+            });
+        }
+        for_each_function(&snippets_data.functions, &mut |function| {
+            for metric in &function.exceeded {
+                findings.push(Finding {
+                    qualified_name: &function.location.qualified_name,
+                    kind: function.location.kind,
+                    start_line: function.location.start_line,
+                    end_line: function.location.end_line,
+                    start_column: function.location.start_column,
+                    end_column: function.location.end_column,
+                    start_byte: function.location.start_byte,
+                    end_byte: function.location.end_byte,
+                    fingerprint: &function.location.fingerprint,
+                    ..finding(*metric, function.metrics[metric])
+                });
+            }
+        });
+        for suppressed in &snippets_data.suppressed {
+            findings.push(Finding {
+                qualified_name: &suppressed.location.qualified_name,
+                kind: suppressed.location.kind,
+                start_line: suppressed.location.start_line,
+                end_line: suppressed.location.end_line,
+                start_column: suppressed.location.start_column,
+                end_column: suppressed.location.end_column,
+                start_byte: suppressed.location.start_byte,
+                end_byte: suppressed.location.end_byte,
+                fingerprint: &suppressed.location.fingerprint,
+                suppression: Some(&suppressed.suppression),
+                ..finding(suppressed.metric, suppressed.complexity)
+            });
+        }
+    }
+    findings.sort_by(|a, b| {
+        (a.source_path, a.start_line, a.metric).cmp(&(b.source_path, b.start_line, b.metric))
+    });
+    findings
+}
+
+// Visits some snippets together with their nested snippets.
+fn for_each_snippet<'a>(snippets: &'a [SnippetData], f: &mut impl FnMut(&'a SnippetData)) {
+    for snippet in snippets {
+        f(snippet);
+        for_each_snippet(&snippet.details.children, f);
+    }
+}
+
+// Visits some functions together with their nested functions.
+fn for_each_function<'a>(functions: &'a [FunctionData], f: &mut impl FnMut(&'a FunctionData)) {
+    for function in functions {
+        f(function);
+        for_each_function(&function.details.children, f);
+    }
+}
+
+// Number of worst offenders listed in the summary of a report.
+const WORST_OFFENDERS: usize = 10;
+
+// Summary of the snippets of all the analyzed files.
+#[derive(Serialize)]
+pub(crate) struct Summary<'a> {
+    pub(crate) files: Vec<FileSummary>,
+    pub(crate) metrics: Vec<MetricSummary>,
+    pub(crate) worst_offenders: Vec<Offender<'a>>,
+}
+
+// Number of snippets of a source file.
+#[derive(Serialize)]
+pub(crate) struct FileSummary {
+    pub(crate) path: String,
+    pub(crate) language: &'static str,
+    pub(crate) snippets: usize,
+    pub(crate) suppressed: usize,
+}
+
+// Number of snippets, and of files containing them, of a metric.
+#[derive(Serialize)]
+pub(crate) struct MetricSummary {
+    pub(crate) metric: Complexity,
+    #[serde(serialize_with = "serialize_value")]
+    pub(crate) threshold: f64,
+    pub(crate) snippets: usize,
+    pub(crate) files: usize,
+}
+
+// A space whose metric exceeds its threshold by far.
+#[derive(Serialize)]
+pub(crate) struct Offender<'a> {
+    pub(crate) path: String,
+    pub(crate) qualified_name: &'a str,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+    pub(crate) metric: Complexity,
+    #[serde(serialize_with = "serialize_value")]
+    pub(crate) value: f64,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_value"
+    )]
+    pub(crate) threshold: Option<f64>,
+}
+
+impl<'a> Summary<'a> {
+    // Summarizes the source files containing complex code, sorted by path.
+    pub(crate) fn new(snippets: &[&'a Snippets], config: &ReportConfig<'a>) -> Self {
+        let mut files = Vec::with_capacity(snippets.len());
+        let mut metrics = config
+            .thresholds
+            .iter()
+            .map(|(metric, threshold)| MetricSummary {
+                metric: *metric,
+                threshold: *threshold,
+                snippets: 0,
+                files: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut offenders = Vec::new();
+
+        for snippets_data in snippets {
+            // Suppressed snippets are accepted, so they are only counted.
+            let (suppressed, findings): (Vec<_>, Vec<_>) =
+                findings(std::slice::from_ref(*snippets_data), config)
+                    .into_iter()
+                    .partition(|finding| finding.suppression.is_some());
+            for metric_summary in metrics.iter_mut() {
+                let snippets = findings
+                    .iter()
+                    .filter(|finding| finding.metric == metric_summary.metric)
+                    .count();
+                metric_summary.snippets += snippets;
+                metric_summary.files += usize::from(snippets > 0);
+            }
+            files.push(FileSummary {
+                path: relative_path(&snippets_data.source_path, config.root),
+                language: snippets_data.language.name(),
+                snippets: findings.len(),
+                suppressed: suppressed.len(),
+            });
+            offenders.extend(findings);
+        }
+
+        // The findings whose excess is unknown are the least offending.
+        offenders.sort_by(|a, b| {
+            let excess = |finding: &Finding| finding.excess().unwrap_or(f64::NEG_INFINITY);
+            excess(b).total_cmp(&excess(a))
+        });
+        let worst_offenders = offenders
+            .iter()
+            .take(WORST_OFFENDERS)
+            .map(|finding| Offender {
+                path: finding.path(),
+                qualified_name: finding.qualified_name,
+                start_line: finding.start_line,
+                end_line: finding.end_line,
+                metric: finding.metric,
+                value: finding.value,
+                threshold: finding.threshold,
+            })
+            .collect();
+
+        Self {
+            files,
+            metrics,
+            worst_offenders,
+        }
+    }
+}
+
+// Configuration of the reports.
+pub(crate) struct ReportConfig<'a> {
+    // Absolute threshold of each analyzed metric.
+    pub(crate) thresholds: &'a [(Complexity, f64)],
+    // Whether every analyzed function is reported together with all its
+    // metrics.
+    pub(crate) all_functions: bool,
+    // Whether the markdown, html and json outputs write a single report
+    // instead of a file for each source file.
+    pub(crate) single_file: bool,
+    // Every analyzed space of each source file, recorded only for the
+    // formats which report also the spaces below the thresholds.
+    pub(crate) spaces: &'a [FileSpaces],
+    // Directory which the paths of the source files are relative to.
+    pub(crate) root: &'a Path,
+}
+
+// Formats which write a single report for all the analyzed files.
+pub(crate) trait WriteReport {
+    const FILENAME: &'static str;
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()>;
+
+    fn write_format(path: &Path, snippets: &[Snippets], config: &ReportConfig) -> Result<()> {
+        create_dir_all(path)?;
+        let final_path = path.join(Self::FILENAME);
+        debug!("Creating {:?}", final_path);
+
+        let mut writer = BufWriter::new(File::create(final_path)?);
+        Self::write_report(&mut writer, snippets, config)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// Value of a metric as a JSON number, integral values are integers.
+fn number(value: f64) -> serde_json::Value {
+    serialize_value(&value, serde_json::value::Serializer).unwrap_or_default()
+}
+
+pub(crate) struct Sarif;
+
+impl WriteReport for Sarif {
+    const FILENAME: &'static str = "complex-code-spotter.sarif";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let rules = Complexity::all()
+            .iter()
+            .map(|metric| {
+                json!({
+                    "id": metric.name(),
+                    "name": metric.to_string(),
+                    "shortDescription": { "text": metric.description() },
+                    "defaultConfiguration": {
+                        "level": "warning",
+                        "parameters": { "threshold": number(metric.default_threshold()) },
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = findings(snippets, config)
+            .iter()
+            .map(|finding| {
+                let mut region = json!({
+                    "startLine": finding.start_line,
+                    "endLine": finding.end_line,
+                });
+                // Columns and offsets are unknown when the syntax tree is not
+                // available.
+                if finding.start_column > 0 {
+                    region["startColumn"] = json!(finding.start_column);
+                    region["endColumn"] = json!(finding.end_column);
+                }
+                if finding.end_byte > finding.start_byte {
+                    region["byteOffset"] = json!(finding.start_byte);
+                    region["byteLength"] = json!(finding.end_byte - finding.start_byte);
+                }
+                let mut result = json!({
+                    "ruleId": finding.metric.name(),
+                    "ruleIndex": Complexity::all()
+                        .iter()
+                        .position(|metric| *metric == finding.metric),
+                    "level": "warning",
+                    "message": { "text": finding.message() },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": finding.path() },
+                            "region": region,
+                        },
+                        "logicalLocations": [{
+                            "fullyQualifiedName": finding.qualified_name,
+                            "kind": finding.kind.to_string().to_lowercase(),
+                        }],
+                    }],
+                    "partialFingerprints": { "complexCodeSpotter/v1": finding.fingerprint },
+                    "properties": {
+                        "value": number(finding.value),
+                        "threshold": finding.threshold.map(number),
+                    },
+                });
+                if let Some(suppression) = finding.suppression {
+                    result["suppressions"] = json!([{
+                        "kind": "inSource",
+                        "justification": suppression.reason,
+                    }]);
+                }
+                result
+            })
+            .collect::<Vec<_>>();
+
+        let log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+        serde_json::to_writer_pretty(writer, &log)?;
+        Ok(())
+    }
+}
+
+pub(crate) struct CodeClimate;
+
+impl CodeClimate {
+    // Severity of an issue, the more the value exceeds the threshold the
+    // higher the severity.
+    fn severity(finding: &Finding) -> &'static str {
+        match finding.excess() {
+            None => "major",
+            Some(excess) if excess < 0.25 => "minor",
+            Some(excess) if excess < 0.5 => "major",
+            Some(excess) if excess < 1. => "critical",
+            Some(_) => "blocker",
+        }
+    }
+
+    // Since a space can exceed more than one threshold, the fingerprint of
+    // an issue also hashes its metric.
+    fn fingerprint(finding: &Finding) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(finding.fingerprint.as_bytes());
+        hasher.update([0]);
+        hasher.update(finding.metric.name().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl WriteReport for CodeClimate {
+    const FILENAME: &'static str = "complex-code-spotter-codeclimate.json";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        // Suppressed snippets are accepted, so they are not issues.
+        let issues = findings(snippets, config)
+            .iter()
+            .filter(|finding| finding.suppression.is_none())
+            .map(|finding| {
+                json!({
+                    "type": "issue",
+                    "check_name": finding.metric.name(),
+                    "description": finding.message(),
+                    "categories": ["Complexity"],
+                    "location": {
+                        "path": finding.path(),
+                        "lines": {
+                            "begin": finding.start_line,
+                            "end": finding.end_line,
+                        },
+                    },
+                    "severity": Self::severity(finding),
+                    "fingerprint": Self::fingerprint(finding),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(writer, &issues)?;
+        Ok(())
+    }
+}
+
+// Escapes a text used in XML content or in a double-quoted XML attribute.
+// Characters which are not allowed in XML documents are removed.
+fn xml(text: &str) -> String {
+    html_escape::encode_double_quoted_attribute(text)
+        .chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || !c.is_control())
+        .collect()
+}
+
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+pub(crate) struct Checkstyle;
+
+impl WriteReport for Checkstyle {
+    const FILENAME: &'static str = "complex-code-spotter-checkstyle.xml";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let mut snippets = snippets.iter().collect::<Vec<_>>();
+        snippets.sort_by(|a, b| a.source_path.cmp(&b.source_path));
+
+        writeln!(writer, "{XML_DECLARATION}")?;
+        writeln!(writer, r#"<checkstyle version="4.3">"#)?;
+        for snippets_data in snippets {
+            writeln!(
+                writer,
+                r#"  <file name="{}">"#,
+                xml(&relative_path(&snippets_data.source_path, config.root))
+            )?;
+            // Suppressed snippets are accepted, so they are not errors.
+            for finding in findings(std::slice::from_ref(snippets_data), config)
+                .iter()
+                .filter(|finding| finding.suppression.is_none())
+            {
+                writeln!(
+                    writer,
+                    r#"    <error line="{}" severity="warning" message="{}" source="{}.{}"/>"#,
+                    finding.start_line,
+                    xml(&finding.message()),
+                    env!("CARGO_PKG_NAME"),
+                    finding.metric.name()
+                )?;
+            }
+            writeln!(writer, "  </file>")?;
+        }
+        writeln!(writer, "</checkstyle>")?;
+        Ok(())
+    }
+}
+
+pub(crate) struct JUnit;
+
+// Outcome of the test case of a space.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Passed,
+    // Messages of the exceeded thresholds.
+    Failed(Vec<String>),
+    // Reasons of the suppressions.
+    Skipped(Vec<String>),
+}
+
+impl JUnit {
+    // Outcome of each function of a source file. A function fails when its
+    // metrics exceed some thresholds, and it is skipped when all its
+    // exceeded thresholds are suppressed.
+    fn outcomes<'a>(
+        file: &'a FileSpaces,
+        snippets: Option<&Snippets>,
+        thresholds: &[(Complexity, f64)],
+    ) -> Vec<(&'a SpaceData, Outcome)> {
+        let mut suppressed_map: HashMap<(usize, &str), Vec<&SuppressedData>> = HashMap::new();
+        for suppressed in snippets.iter().flat_map(|snippets| &snippets.suppressed) {
+            suppressed_map
+                .entry((
+                    suppressed.location.start_line,
+                    suppressed.location.fingerprint.as_str(),
+                ))
+                .or_default()
+                .push(suppressed);
+        }
+        file.spaces
+            .iter()
+            .filter(|space| space.location.kind == SpaceKind::Function)
+            .map(|space| {
+                let suppressed = suppressed_map
+                    .remove(&(
+                        space.location.start_line,
+                        space.location.fingerprint.as_str(),
+                    ))
+                    .unwrap_or_default();
+                let exceeded = thresholds.iter().filter_map(|(metric, threshold)| {
+                    let value = *space.metrics.get(metric)?;
+                    metric
+                        .exceeds(value, *threshold)
+                        .then_some((*metric, value, *threshold))
+                });
+                let (suppressed_metrics, failures): (Vec<_>, Vec<_>) =
+                    exceeded.partition(|(metric, _, _)| {
+                        suppressed
+                            .iter()
+                            .any(|suppressed| suppressed.metric == *metric)
+                    });
+                let outcome = if !failures.is_empty() {
+                    Outcome::Failed(
+                        failures
+                            .into_iter()
+                            .map(|(metric, value, threshold)| {
+                                message(
+                                    metric,
+                                    &space.location.qualified_name,
+                                    value,
+                                    Some(threshold),
+                                )
+                            })
+                            .collect(),
+                    )
+                } else if !suppressed_metrics.is_empty() {
+                    Outcome::Skipped(
+                        suppressed
+                            .iter()
+                            .filter_map(|suppressed| suppressed.suppression.reason.clone())
+                            .collect(),
+                    )
+                } else {
+                    Outcome::Passed
+                };
+                (space, outcome)
+            })
+            .collect()
+    }
+
+    // Number of failed and skipped test cases.
+    fn count(outcomes: &[(&SpaceData, Outcome)]) -> (usize, usize) {
+        outcomes
+            .iter()
+            .fold((0, 0), |(failures, skipped), (_, outcome)| match outcome {
+                Outcome::Passed => (failures, skipped),
+                Outcome::Failed(_) => (failures + 1, skipped),
+                Outcome::Skipped(_) => (failures, skipped + 1),
+            })
+    }
+}
+
+impl WriteReport for JUnit {
+    const FILENAME: &'static str = "complex-code-spotter-junit.xml";
+
+    fn write_report(
+        writer: &mut dyn Write,
+        snippets: &[Snippets],
+        config: &ReportConfig,
+    ) -> Result<()> {
+        // Each analyzed file is a test suite, also when it is clean.
+        let complex_files = snippets
+            .iter()
+            .map(|snippets_data| (snippets_data.source_path.as_path(), snippets_data))
+            .collect::<HashMap<_, _>>();
+        let mut files = config.spaces.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| a.source_path.cmp(&b.source_path));
+        let suites = files
+            .into_iter()
+            .map(|file| {
+                let snippets = complex_files.get(file.source_path.as_path()).copied();
+                (file, Self::outcomes(file, snippets, config.thresholds))
+            })
+            .collect::<Vec<_>>();
+
+        let tests = suites
+            .iter()
+            .map(|(_, outcomes)| outcomes.len())
+            .sum::<usize>();
+        let (failures, skipped) = suites.iter().fold((0, 0), |(f, s), (_, outcomes)| {
+            let (failures, skipped) = Self::count(outcomes);
+            (f + failures, s + skipped)
+        });
+
+        writeln!(writer, "{XML_DECLARATION}")?;
+        writeln!(
+            writer,
+            r#"<testsuites name="{}" tests="{tests}" failures="{failures}" skipped="{skipped}">"#,
+            env!("CARGO_PKG_NAME"),
+        )?;
+        for (file, outcomes) in suites {
+            let path = xml(&relative_path(&file.source_path, config.root));
+            let (failures, skipped) = Self::count(&outcomes);
+            writeln!(
+                writer,
+                r#"  <testsuite name="{path}" tests="{}" failures="{failures}" skipped="{skipped}">"#,
+                outcomes.len(),
+            )?;
+            for (space, outcome) in outcomes {
+                let testcase = format!(
+                    r#"<testcase name="{}" classname="{path}" file="{path}" line="{}""#,
+                    xml(&space.location.qualified_name),
+                    space.location.start_line,
+                );
+                match outcome {
+                    Outcome::Passed => writeln!(writer, "    {testcase}/>")?,
+                    Outcome::Failed(messages) => {
+                        writeln!(writer, "    {testcase}>")?;
+                        writeln!(
+                            writer,
+                            r#"      <failure message="{}" type="complexity">{}</failure>"#,
+                            xml(&messages.join("; ")),
+                            xml(&messages.join("\n")),
+                        )?;
+                        writeln!(writer, "    </testcase>")?;
+                    }
+                    Outcome::Skipped(reasons) => {
+                        let message = if reasons.is_empty() {
+                            "suppressed".to_string()
+                        } else {
+                            reasons.join("; ")
+                        };
+                        writeln!(writer, "    {testcase}>")?;
+                        writeln!(writer, r#"      <skipped message="{}"/>"#, xml(&message))?;
+                        writeln!(writer, "    </testcase>")?;
+                    }
+                }
+            }
+            writeln!(writer, "  </testsuite>")?;
+        }
+        writeln!(writer, "</testsuites>")?;
+        Ok(())
+    }
+}
+
+// Formats which write a table, with a row for each snippet, or a row for
+// each analyzed function when every function is reported.
+trait WriteTable {
+    const FILENAME: &'static str;
+    const DELIMITER: char;
+
+    // Field of a row, escaped so it does not break the row.
+    fn field(text: &str) -> Cow<'_, str>;
+
+    fn write_row<S: AsRef<str>>(
+        writer: &mut dyn Write,
+        fields: impl IntoIterator<Item = S>,
+    ) -> Result<()> {
+        for (index, field) in fields.into_iter().enumerate() {
+            if index > 0 {
+                write!(writer, "{}", Self::DELIMITER)?;
+            }
+            write!(writer, "{}", Self::field(field.as_ref()))?;
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    fn write_header(writer: &mut dyn Write, all_functions: bool) -> Result<()> {
+        if all_functions {
+            let header = ["path", "language", "name", "kind", "start_line", "end_line"]
+                .into_iter()
+                .chain(Complexity::all().iter().map(Complexity::name))
+                .chain(["exceeded"]);
+            Self::write_row(writer, header)
+        } else {
+            Self::write_row(
+                writer,
+                [
+                    "path",
+                    "language",
+                    "name",
+                    "kind",
+                    "start_line",
+                    "end_line",
+                    "metric",
+                    "value",
+                    "threshold",
+                ],
+            )
+        }
+    }
+
+    fn write_snippets(
+        writer: &mut dyn Write,
+        snippets_data: &Snippets,
+        config: &ReportConfig,
+    ) -> Result<()> {
+        // Suppressed snippets are accepted, so they are not reported.
+        for finding in findings(std::slice::from_ref(snippets_data), config)
+            .iter()
+            .filter(|finding| finding.suppression.is_none())
+        {
+            Self::write_row(
+                writer,
+                [
+                    finding.path(),
+                    snippets_data.language.name().to_string(),
+                    finding.qualified_name.to_string(),
+                    finding.kind.to_string().to_lowercase(),
+                    finding.start_line.to_string(),
+                    finding.end_line.to_string(),
+                    finding.metric.name().to_string(),
+                    finding.value.to_string(),
+                    finding
+                        .threshold
+                        .map_or_else(String::new, |t| t.to_string()),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_functions(
+        writer: &mut dyn Write,
+        file: &FileSpaces,
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let path = relative_path(&file.source_path, config.root);
+        for space in &file.spaces {
+            // Metrics which do not apply to the space are empty.
+            let metrics = Complexity::all().iter().map(|metric| {
+                space
+                    .metrics
+                    .get(metric)
+                    .map_or_else(String::new, |value| value.to_string())
+            });
+            let exceeded = config
+                .thresholds
+                .iter()
+                .filter(|(metric, threshold)| {
+                    space
+                        .metrics
+                        .get(metric)
+                        .is_some_and(|value| metric.exceeds(*value, *threshold))
+                })
+                .map(|(metric, _)| metric.name())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let row = [
+                path.clone(),
+                file.language.name().to_string(),
+                space.location.qualified_name.clone(),
+                space.location.kind.to_string().to_lowercase(),
+                space.location.start_line.to_string(),
+                space.location.end_line.to_string(),
+            ]
+            .into_iter()
+            .chain(metrics)
+            .chain([exceeded]);
+            Self::write_row(writer, row)?;
+        }
+        Ok(())
+    }
+}
+
+// Table streamed while the files are analyzed. The header is written when
+// the table is created, and the rows of each file as soon as the file has
+// been analyzed, so rows are not kept in memory and they follow the order
+// in which files are analyzed.
+#[derive(Debug)]
+struct TableStream<T, W> {
+    writer: Mutex<W>,
+    table: PhantomData<T>,
+}
+
+impl<T: WriteTable, W: Write> TableStream<T, W> {
+    fn new(mut writer: W, all_functions: bool) -> Result<Self> {
+        T::write_header(&mut writer, all_functions)?;
+        writer.flush()?;
+        Ok(Self {
+            writer: Mutex::new(writer),
+            table: PhantomData,
+        })
+    }
+
+    // Writes the rows of an analyzed file, i.e. its snippets, or all its
+    // spaces when every function is reported.
+    fn write_file(
+        &self,
+        snippets: Option<&Snippets>,
+        spaces: Option<&FileSpaces>,
+        config: &ReportConfig,
+    ) -> Result<()> {
+        let mut writer = self.writer.lock()?;
+        match (config.all_functions, snippets, spaces) {
+            (true, _, Some(spaces)) => T::write_functions(&mut *writer, spaces, config)?,
+            (false, Some(snippets), _) => T::write_snippets(&mut *writer, snippets, config)?,
+            _ => {}
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// Tables of the csv and tsv formats.
+#[derive(Debug)]
+pub(crate) struct Tables {
+    csv: Option<TableStream<Csv, BufWriter<File>>>,
+    tsv: Option<TableStream<Tsv, BufWriter<File>>>,
+}
+
+impl Tables {
+    // Creates the selected tables in a directory, writing their headers.
+    pub(crate) fn create(path: &Path, csv: bool, tsv: bool, all_functions: bool) -> Result<Self> {
+        fn create<T: WriteTable>(
+            path: &Path,
+            all_functions: bool,
+        ) -> Result<TableStream<T, BufWriter<File>>> {
+            create_dir_all(path)?;
+            let final_path = path.join(T::FILENAME);
+            debug!("Creating {:?}", final_path);
+            TableStream::new(BufWriter::new(File::create(final_path)?), all_functions)
+        }
+
+        Ok(Self {
+            csv: csv
+                .then(|| create::<Csv>(path, all_functions))
+                .transpose()?,
+            tsv: tsv
+                .then(|| create::<Tsv>(path, all_functions))
+                .transpose()?,
+        })
+    }
+
+    // Writes the rows of an analyzed file in every table.
+    pub(crate) fn write_file(
+        &self,
+        snippets: Option<&Snippets>,
+        spaces: Option<&FileSpaces>,
+        config: &ReportConfig,
+    ) -> Result<()> {
+        if let Some(csv) = &self.csv {
+            csv.write_file(snippets, spaces, config)?;
+        }
+        if let Some(tsv) = &self.tsv {
+            tsv.write_file(snippets, spaces, config)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Csv;
+
+impl WriteTable for Csv {
+    const FILENAME: &'static str = "complex-code-spotter.csv";
+    const DELIMITER: char = ',';
+
+    // Fields containing delimiters, quotes or line breaks are quoted.
+    fn field(text: &str) -> Cow<'_, str> {
+        if text.contains([',', '"', '\n', '\r']) {
+            Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Tsv;
+
+impl WriteTable for Tsv {
+    const FILENAME: &'static str = "complex-code-spotter.tsv";
+    const DELIMITER: char = '\t';
+
+    // Fields cannot be quoted, so tabs and line breaks become spaces.
+    fn field(text: &str) -> Cow<'_, str> {
+        if text.contains(['\t', '\n', '\r']) {
+            Cow::Owned(text.replace(['\t', '\n', '\r'], " "))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rust_code_analysis::{get_function_spaces, LANG};
+
+    use super::*;
+    use crate::metrics::{MetricsOptions, MiVariant};
+    use crate::snippets::{
+        get_code_snippets, AnalyzedFile, Hierarchy, Language, Layout, SnippetsOptions, SpaceFilter,
+        SpaceLocation,
+    };
+
+    const SOURCE: &str = "fn simple() {}
+
+fn branches(x: i32) -> i32 {
+    if x > 0 {
+        if x > 10 {
+            return 10;
+        }
+        return 1;
+    }
+    0
+}
+";
+
+    const THRESHOLDS: [(Complexity, f64); 2] =
+        [(Complexity::Cyclomatic, 2.), (Complexity::Cognitive, 2.)];
+
+    // Writes the report of a Rust source file. The file has an absolute
+    // path, which is reported relative to the root.
+    fn write(
+        all_functions: bool,
+        write: impl FnOnce(&mut Vec<u8>, &[Snippets], &ReportConfig),
+    ) -> String {
+        let root = Path::new("/project");
+        let path = root.join("src").join("lib.rs");
+        let space =
+            get_function_spaces(&LANG::Rust, SOURCE.as_bytes().to_vec(), &path, None).unwrap();
+        let file = AnalyzedFile::new(path, SOURCE.to_owned(), LANG::Rust, space);
+        let metrics = MetricsOptions {
+            mi_variant: MiVariant::VisualStudio,
+            composite: None,
+        };
+        let options = SnippetsOptions {
+            layout: Layout::Metric,
+            hierarchy: Hierarchy::Flat,
+            filter: SpaceFilter::default(),
+            context_lines: 0,
+            max_lines: None,
+            text: true,
+            explain: false,
+            spaces: true,
+            all_metrics: all_functions,
+            root: root.to_path_buf(),
+        };
+        let (snippets, spaces) = get_code_snippets(&file, &THRESHOLDS, &metrics, &options);
+        let config = ReportConfig {
+            thresholds: &THRESHOLDS,
+            all_functions,
+            single_file: false,
+            spaces: &[spaces.unwrap()],
+            root,
+        };
+        let mut report = Vec::new();
+        write(&mut report, &Vec::from_iter(snippets), &config);
+        String::from_utf8(report).unwrap()
+    }
+
+    // Writes the report of a Rust source file in a format.
+    fn report<W: WriteReport>(all_functions: bool) -> String {
+        write(all_functions, |writer, snippets, config| {
+            W::write_report(writer, snippets, config).unwrap();
+        })
+    }
+
+    // Streams the table of a Rust source file in a format.
+    fn table<T: WriteTable>(all_functions: bool) -> String {
+        write(all_functions, |writer, snippets, config| {
+            TableStream::<T, _>::new(writer, all_functions)
+                .unwrap()
+                .write_file(snippets.first(), config.spaces.first(), config)
+                .unwrap();
+        })
+    }
+
+    #[test]
+    fn sarif_results() {
+        let log: serde_json::Value = serde_json::from_str(&report::<Sarif>(false)).unwrap();
+        let run = &log["runs"][0];
+
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().map(Vec::len),
+            Some(Complexity::all().len())
+        );
+        assert_eq!(run["results"].as_array().map(Vec::len), Some(2));
+        assert_eq!(
+            run["results"][1],
+            json!({
+                "ruleId": "cognitive",
+                "ruleIndex": 1,
+                "level": "warning",
+                "message": {
+                    "text": "Cognitive complexity of `fn branches` is 3, above the threshold of 2"
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/lib.rs" },
+                        "region": {
+                            "startLine": 3,
+                            "endLine": 11,
+                            "startColumn": 1,
+                            "endColumn": 2,
+                            "byteOffset": 16,
+                            "byteLength": 128
+                        }
+                    },
+                    "logicalLocations": [{
+                        "fullyQualifiedName": "fn branches",
+                        "kind": "function"
+                    }]
+                }],
+                "partialFingerprints": {
+                    "complexCodeSpotter/v1":
+                        "6843f39d698307f63fb5d1f68a1d9861ddc08a4a84a12d5971a04dfce950cbd3"
+                },
+                "properties": { "value": 3, "threshold": 2 }
+            })
+        );
+    }
+
+    #[test]
+    fn code_climate_issues() {
+        let issues: serde_json::Value =
+            serde_json::from_str(&report::<CodeClimate>(false)).unwrap();
+        let issue = |check_name, description, fingerprint| {
+            json!({
+                "type": "issue",
+                "check_name": check_name,
+                "description": description,
+                "categories": ["Complexity"],
+                "location": {
+                    "path": "src/lib.rs",
+                    "lines": { "begin": 3, "end": 11 }
+                },
+                "severity": "critical",
+                "fingerprint": fingerprint
+            })
+        };
+
+        assert_eq!(
+            issues,
+            json!([
+                issue(
+                    "cyclomatic",
+                    "Cyclomatic complexity of `fn branches` is 3, above the threshold of 2",
+                    "43bbaf25255e9f65e00c3212b2e053092fd593ee665288999cb408c4aaa80ec5"
+                ),
+                issue(
+                    "cognitive",
+                    "Cognitive complexity of `fn branches` is 3, above the threshold of 2",
+                    "140a1da5e951edaedc7a1995f52f96bc1de9107a23f3a841b9fe2a42ed768108"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn checkstyle_report() {
+        assert_eq!(
+            report::<Checkstyle>(false),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="4.3">
+  <file name="src/lib.rs">
+    <error line="3" severity="warning" message="Cyclomatic complexity of `fn branches` is 3, above the threshold of 2" source="complex-code-spotter.cyclomatic"/>
+    <error line="3" severity="warning" message="Cognitive complexity of `fn branches` is 3, above the threshold of 2" source="complex-code-spotter.cognitive"/>
+  </file>
+</checkstyle>
+"#
+        );
+    }
+
+    #[test]
+    fn junit_report() {
+        assert_eq!(
+            report::<JUnit>(false),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="complex-code-spotter" tests="2" failures="1" skipped="0">
+  <testsuite name="src/lib.rs" tests="2" failures="1" skipped="0">
+    <testcase name="fn simple" classname="src/lib.rs" file="src/lib.rs" line="1"/>
+    <testcase name="fn branches" classname="src/lib.rs" file="src/lib.rs" line="3">
+      <failure message="Cyclomatic complexity of `fn branches` is 3, above the threshold of 2; Cognitive complexity of `fn branches` is 3, above the threshold of 2" type="complexity">Cyclomatic complexity of `fn branches` is 3, above the threshold of 2
+Cognitive complexity of `fn branches` is 3, above the threshold of 2</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn csv_report() {
+        assert_eq!(
+            table::<Csv>(false),
+            "path,language,name,kind,start_line,end_line,metric,value,threshold
+src/lib.rs,rust,fn branches,function,3,11,cyclomatic,3,2
+src/lib.rs,rust,fn branches,function,3,11,cognitive,3,2
+"
+        );
+    }
+
+    #[test]
+    fn tsv_all_functions() {
+        let table = table::<Tsv>(true);
+        let rows = table
+            .lines()
+            .map(|row| row.split('\t').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        // Path, name, cyclomatic, cognitive and exceeded columns.
+        let columns = rows
+            .iter()
+            .map(|row| [row[0], row[2], row[6], row[7], row[row.len() - 1]])
+            .collect::<Vec<_>>();
+
+        assert!(rows
+            .iter()
+            .all(|row| row.len() == 7 + Complexity::all().len()));
+        assert_eq!(
+            columns,
+            [
+                ["path", "name", "cyclomatic", "cognitive", "exceeded"],
+                ["src/lib.rs", "fn simple", "1", "0", ""],
+                [
+                    "src/lib.rs",
+                    "fn branches",
+                    "3",
+                    "3",
+                    "cyclomatic cognitive"
+                ],
+            ]
+        );
+    }
+
+    fn finding(metric: Complexity, value: f64, threshold: f64) -> Finding<'static> {
+        Finding {
+            source_path: Path::new("src/main.rs"),
+            root: Path::new(""),
+            metric,
+            value,
+            threshold: Some(threshold),
+            qualified_name: "fn main",
+            kind: SpaceKind::Function,
+            start_line: 1,
+            end_line: 10,
+            start_column: 1,
+            end_column: 2,
+            start_byte: 0,
+            end_byte: 100,
+            fingerprint: "0123",
+            suppression: None,
+        }
+    }
+
+    #[test]
+    fn code_climate_severity() {
+        let severity =
+            |metric, value, threshold| CodeClimate::severity(&finding(metric, value, threshold));
+        assert_eq!(severity(Complexity::Cognitive, 16., 15.), "minor");
+        assert_eq!(severity(Complexity::Cognitive, 20., 15.), "major");
+        assert_eq!(severity(Complexity::Cognitive, 25., 15.), "critical");
+        assert_eq!(severity(Complexity::Cognitive, 30., 15.), "blocker");
+        // Lower values of the maintainability index are worse.
+        assert_eq!(
+            severity(Complexity::MaintainabilityIndex, 5., 20.),
+            "critical"
+        );
+        assert_eq!(severity(Complexity::Cyclomatic, 3., 0.), "major");
+    }
+
+    #[test]
+    fn code_climate_fingerprint() {
+        let cognitive = finding(Complexity::Cognitive, 20., 15.);
+        let cyclomatic = finding(Complexity::Cyclomatic, 20., 15.);
+        assert_eq!(
+            CodeClimate::fingerprint(&cognitive),
+            CodeClimate::fingerprint(&finding(Complexity::Cognitive, 30., 10.))
+        );
+        assert_ne!(
+            CodeClimate::fingerprint(&cognitive),
+            CodeClimate::fingerprint(&cyclomatic)
+        );
+    }
+
+    #[test]
+    fn table_fields() {
+        assert_eq!(Csv::field("fn main"), "fn main");
+        assert_eq!(
+            Csv::field("impl Foo<A, B> > fn \"new\""),
+            "\"impl Foo<A, B> > fn \"\"new\"\"\""
+        );
+        assert_eq!(Tsv::field("fn\tmain\n"), "fn main ");
+
+        let mut row = Vec::new();
+        Csv::write_row(&mut row, ["a", "b,c", ""]).unwrap();
+        assert_eq!(String::from_utf8(row).unwrap(), "a,\"b,c\",\n");
+    }
+
+    #[test]
+    fn junit_outcomes() {
+        let location = |kind, name: &str, start_line| SpaceLocation {
+            name: name.to_owned(),
+            kind,
+            qualified_name: format!("fn {name}"),
+            start_line,
+            end_line: start_line + 5,
+            start_column: 1,
+            end_column: 2,
+            start_byte: 0,
+            end_byte: 0,
+            fingerprint: name.to_owned(),
+        };
+        let space = |kind, name: &str, start_line, metrics: &[(Complexity, f64)]| SpaceData {
+            location: location(kind, name, start_line),
+            metrics: metrics.iter().copied().collect(),
+        };
+        let file = FileSpaces {
+            source_path: PathBuf::from("src/main.rs"),
+            language: Language::Rust,
+            spaces: vec![
+                space(
+                    SpaceKind::Unit,
+                    "main.rs",
+                    1,
+                    &[(Complexity::Cyclomatic, 20.)],
+                ),
+                space(
+                    SpaceKind::Function,
+                    "simple",
+                    1,
+                    &[(Complexity::Cyclomatic, 2.)],
+                ),
+                space(
+                    SpaceKind::Function,
+                    "complex",
+                    10,
+                    &[(Complexity::Cyclomatic, 12.)],
+                ),
+                space(
+                    SpaceKind::Function,
+                    "accepted",
+                    20,
+                    &[(Complexity::Cyclomatic, 11.)],
+                ),
+                space(
+                    SpaceKind::Closure,
+                    "closure",
+                    22,
+                    &[(Complexity::Cyclomatic, 11.)],
+                ),
+            ],
+        };
+        let suppression = SuppressionData {
+            line: 19,
+            metrics: vec![Complexity::Cyclomatic],
+            reason: Some("parser".to_owned()),
+            error: None,
+        };
+        let snippets = Snippets {
+            source_path: file.source_path.clone(),
+            language: Language::Rust,
+            snippets: HashMap::new(),
+            functions: Vec::new(),
+            suppressed: vec![SuppressedData {
+                location: location(SpaceKind::Function, "accepted", 20),
+                metric: Complexity::Cyclomatic,
+                complexity: 11.,
+                suppression,
+            }],
+            unused_suppressions: Vec::new(),
+        };
+
+        // Only functions are test cases, which fail or are skipped from the
+        // thresholds they exceed.
+        let outcomes = JUnit::outcomes(&file, Some(&snippets), &[(Complexity::Cyclomatic, 10.)]);
+        assert_eq!(JUnit::count(&outcomes), (1, 1));
+        let outcomes = outcomes
+            .iter()
+            .map(|(space, outcome)| (space.location.name.as_str(), outcome))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                ("simple", &Outcome::Passed),
+                (
+                    "complex",
+                    &Outcome::Failed(vec![
+                        "Cyclomatic complexity of `fn complex` is 12, above the threshold of 10"
+                            .to_owned()
+                    ])
+                ),
+                ("accepted", &Outcome::Skipped(vec!["parser".to_owned()])),
+            ]
+        );
+    }
+
+    #[test]
+    fn xml_escape() {
+        assert_eq!(
+            xml("impl<'a> Parser<'a> > fn \"parse\" & \u{1b}check\n"),
+            "impl&lt;'a&gt; Parser&lt;'a&gt; &gt; fn &quot;parse&quot; &amp; check\n"
+        );
+    }
+}
@@ -1,10 +1,16 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
+use arg_enum_proc_macro::ArgEnum;
 use rust_code_analysis::{FuncSpace, LANG};
-use serde::Serialize;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
 
-use crate::metrics::Complexity;
+use crate::explain::IncrementData;
+use crate::metrics::{methods, Complexity, MetricsOptions};
+use crate::space::{Space, SpaceKind};
+use crate::suppression::SuppressionData;
+use crate::syntax::{parse, NestingData, SpaceSyntax};
 
 /// Supported languages.
 #[derive(Debug, Serialize)]
@@ -66,17 +72,363 @@ impl Language {
     }
 }
 
+// Largest integer which can be represented exactly by a `f64`.
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.;
+
+// Serializes the value of a metric. Integral values are written as
+// integers, so the values of integer metrics, such as the cyclomatic
+// complexity, are written as `15` rather than `15.0`.
+pub(crate) fn serialize_value<S: Serializer>(
+    value: &f64,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if value.fract() == 0. && value.abs() <= MAX_EXACT_INTEGER {
+        serializer.serialize_i64(*value as i64)
+    } else {
+        serializer.serialize_f64(*value)
+    }
+}
+
+// Serializes an optional metric value like `serialize_value`.
+pub(crate) fn serialize_optional_value<S: Serializer>(
+    value: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serialize_value(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+// Serializes the values of some metrics like `serialize_value`.
+fn serialize_values<S: Serializer>(
+    values: &BTreeMap<Complexity, f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    // Values are wrapped, so each one is serialized like a single value.
+    #[derive(Serialize)]
+    struct Value(#[serde(serialize_with = "serialize_value")] f64);
+
+    serializer.collect_map(values.iter().map(|(metric, value)| (metric, Value(*value))))
+}
+
+/// Data of a method contained in a class snippet.
+#[derive(Debug, Serialize)]
+pub struct MethodData {
+    /// Method name.
+    pub name: String,
+    /// Method cyclomatic complexity value.
+    #[serde(serialize_with = "serialize_value")]
+    pub cyclomatic: f64,
+    /// Method start line.
+    pub start_line: usize,
+    /// Method end line.
+    pub end_line: usize,
+}
+
+impl MethodData {
+    fn new(space: &FuncSpace) -> Self {
+        Self {
+            name: space
+                .name
+                .clone()
+                .unwrap_or_else(|| "<anonymous>".to_string()),
+            cyclomatic: space.metrics.cyclomatic.cyclomatic(),
+            start_line: space.start_line,
+            end_line: space.end_line,
+        }
+    }
+}
+
+/// Value of a metric which is a component of a composite score.
+#[derive(Debug, Serialize)]
+pub struct ComponentData {
+    /// Component metric.
+    pub metric: Complexity,
+    /// Component value.
+    #[serde(serialize_with = "serialize_value")]
+    pub value: f64,
+}
+
+/// Lines removed from the middle of a long snippet.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ElisionData {
+    /// First removed line.
+    pub start_line: usize,
+    /// Last removed line.
+    pub end_line: usize,
+}
+
+/// Lines of a nested snippet relative to its parent snippet, whose first
+/// line is line 1.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RelativeLinesData {
+    /// Start line within the parent snippet.
+    pub start_line: usize,
+    /// End line within the parent snippet.
+    pub end_line: usize,
+}
+
+/// Location of a space within its source file.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpaceLocation {
+    /// Space name.
+    pub name: String,
+    /// Space kind.
+    pub kind: SpaceKind,
+    /// Names of the spaces enclosing the space, followed by the name of the
+    /// space itself, e.g. `impl Context > fn new`.
+    pub qualified_name: String,
+    /// Space start line.
+    pub start_line: usize,
+    /// Space end line.
+    pub end_line: usize,
+    /// Space start column, counted in bytes from 1.
+    pub start_column: usize,
+    /// Space end column, counted in bytes from 1 and exclusive.
+    pub end_column: usize,
+    /// Byte offset of the space start in the source file.
+    pub start_byte: usize,
+    /// Byte offset of the space end in the source file, exclusive.
+    pub end_byte: usize,
+    /// Fingerprint of the space, stable across runs as long as the path,
+    /// the qualified name and the code of the space do not change.
+    pub fingerprint: String,
+}
+
 /// Snippets data.
 #[derive(Debug, Serialize)]
 pub struct SnippetData {
     /// Snippet complexity value.
-    pub complexity: usize,
-    /// Snippet start line.
-    pub start_line: usize,
-    /// Snippet end line.
-    pub end_line: usize,
-    /// Snippet text.
+    #[serde(serialize_with = "serialize_value")]
+    pub complexity: f64,
+    /// Location of the space contained in the snippet.
+    #[serde(flatten)]
+    pub location: SpaceLocation,
+    /// Text and details of the snippet.
+    #[serde(flatten)]
+    pub details: SpaceDetails<SnippetData>,
+}
+
+/// Data of a function, or of any other space, which exceeds at least one
+/// threshold.
+#[derive(Debug, Serialize)]
+pub struct FunctionData {
+    /// Space location.
+    #[serde(flatten)]
+    pub location: SpaceLocation,
+    /// Value of each computed metric.
+    #[serde(serialize_with = "serialize_values")]
+    pub metrics: BTreeMap<Complexity, f64>,
+    /// Metrics which exceed their thresholds.
+    pub exceeded: Vec<Complexity>,
+    /// Text and details of the function.
+    #[serde(flatten)]
+    pub details: SpaceDetails<FunctionData>,
+}
+
+/// Text and details of a space, shared by snippets and functions.
+#[derive(Debug, Serialize)]
+pub struct SpaceDetails<T> {
+    /// Space text, empty when text is disabled.
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub text: String,
+    /// Lines preceding the space, shown as context.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub leading_context: String,
+    /// Lines following the space, shown as context.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub trailing_context: String,
+    /// Lines removed from the middle of the text, filled only when the
+    /// space is longer than the maximum number of lines.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elided: Option<ElisionData>,
+    /// Methods breakdown, filled only for class-level metrics.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<MethodData>,
+    /// Components values, filled only for the composite metric.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ComponentData>,
+    /// Maximum nesting depth and its line, filled only for the nesting
+    /// metric.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nesting: Option<NestingData>,
+    /// Lines which increment the selected metrics, filled only in explain
+    /// mode for the cognitive and the cyclomatic complexities.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub explanation: Vec<IncrementData>,
+    /// Lines of the space relative to its parent, filled only for the
+    /// nested spaces of the nested hierarchy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relative_lines: Option<RelativeLinesData>,
+    /// Data of the nested subspaces, filled only for the nested hierarchy.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<T>,
+}
+
+impl<T> SpaceDetails<T> {
+    fn new(text: Text) -> Self {
+        Self {
+            text: text.text,
+            leading_context: text.leading_context,
+            trailing_context: text.trailing_context,
+            elided: text.elided,
+            methods: Vec::new(),
+            components: Vec::new(),
+            nesting: None,
+            explanation: Vec::new(),
+            relative_lines: None,
+            children: Vec::new(),
+        }
+    }
+
+    // Sets the lines of a space relative to its parent space.
+    fn set_relative_lines(&mut self, relative_lines: RelativeLinesData) {
+        self.relative_lines = Some(relative_lines);
+        // The text of a child is already contained in its parent.
+        self.text.clear();
+        self.leading_context.clear();
+        self.trailing_context.clear();
+        self.elided = None;
+    }
+}
+
+/// Data of a space whose metric exceeds its threshold, but which is
+/// suppressed by a comment.
+#[derive(Debug, Serialize)]
+pub struct SuppressedData {
+    /// Space location.
+    #[serde(flatten)]
+    pub location: SpaceLocation,
+    /// Suppressed metric.
+    pub metric: Complexity,
+    /// Metric value.
+    #[serde(serialize_with = "serialize_value")]
+    pub complexity: f64,
+    /// Suppression comment.
+    pub suppression: SuppressionData,
+}
+
+/// Data of an analyzed space, whether it exceeds some thresholds or not.
+#[derive(Debug, Serialize)]
+pub struct SpaceData {
+    /// Space location.
+    #[serde(flatten)]
+    pub location: SpaceLocation,
+    /// Value of each metric which applies to the space, among all the
+    /// metrics when every function is listed, otherwise among the metrics
+    /// with a threshold.
+    #[serde(
+        skip_serializing_if = "BTreeMap::is_empty",
+        serialize_with = "serialize_values"
+    )]
+    pub metrics: BTreeMap<Complexity, f64>,
+}
+
+/// Layouts of the extracted snippets.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Snippets are grouped by metric, so a function which exceeds more
+    /// than one threshold is contained in more than one snippet.
+    #[arg_enum(name = "metric")]
+    Metric,
+    /// Each function appears once, together with the values of all the
+    /// metrics and the list of the exceeded thresholds.
+    #[arg_enum(name = "function")]
+    Function,
+}
+
+impl Layout {
+    /// Default layout.
+    pub const fn default() -> &'static str {
+        "metric"
+    }
+}
+
+/// Policies applied when a space and some of its subspaces exceed the same
+/// threshold.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hierarchy {
+    /// Every space is reported as an independent snippet, so the snippets
+    /// of a space and of its subspaces overlap.
+    #[arg_enum(name = "flat")]
+    Flat,
+    /// The snippets of the subspaces are nested in the snippet of their
+    /// space, with lines relative to it and without duplicated text.
+    #[arg_enum(name = "nested")]
+    Nested,
+    /// Only the innermost spaces are reported.
+    #[arg_enum(name = "leaves")]
+    Leaves,
+    /// Only the outermost spaces are reported.
+    #[arg_enum(name = "outermost")]
+    Outermost,
+}
+
+impl Hierarchy {
+    /// Default hierarchy.
+    pub const fn default() -> &'static str {
+        "flat"
+    }
+}
+
+/// Spaces which can be extracted as snippets.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SpaceFilter {
+    /// Kinds of the spaces which can be extracted as snippets, all kinds
+    /// when empty.
+    pub(crate) kinds: Vec<SpaceKind>,
+    /// Whether test code is excluded from the analysis.
+    pub(crate) exclude_tests: bool,
+}
+
+impl SpaceFilter {
+    // Whether a space is excluded from the analysis together with its
+    // subspaces.
+    fn excludes(&self, space: &Space) -> bool {
+        self.exclude_tests && space.syntax.test
+    }
+
+    // Whether a space can be extracted as a snippet. Spaces of the other
+    // kinds are still visited to find their subspaces.
+    fn is_eligible(&self, space: &Space) -> bool {
+        !self.excludes(space) && (self.kinds.is_empty() || self.kinds.contains(&space.kind))
+    }
+}
+
+// Path of a source file relative to the root directory, with `/` as
+// separator on every platform.
+pub(crate) fn relative_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Options used to extract the snippets.
+#[derive(Debug, Clone)]
+pub(crate) struct SnippetsOptions {
+    /// Layout of the snippets.
+    pub(crate) layout: Layout,
+    /// Hierarchy of the snippets of nested spaces.
+    pub(crate) hierarchy: Hierarchy,
+    /// Spaces which can be extracted as snippets.
+    pub(crate) filter: SpaceFilter,
+    /// Number of lines of context before and after each snippet.
+    pub(crate) context_lines: usize,
+    /// Maximum number of lines of a snippet.
+    pub(crate) max_lines: Option<usize>,
+    /// Whether snippets contain text or only positions.
+    pub(crate) text: bool,
+    /// Whether snippets list the lines which increment the metrics.
+    pub(crate) explain: bool,
+    /// Whether every analyzed space is recorded, also when it does not
+    /// exceed any threshold.
+    pub(crate) spaces: bool,
+    /// Whether the recorded spaces contain the values of all the metrics.
+    pub(crate) all_metrics: bool,
+    /// Directory which the paths of the source files are relative to.
+    pub(crate) root: PathBuf,
 }
 
 /// Snippets of complex code obtained analyzing each complexity metric and
@@ -87,8 +439,21 @@ pub struct Snippets {
     pub source_path: PathBuf,
     /// Source language.
     pub language: Language,
-    /// Snippets contained in the analyzed source file.
+    /// Snippets contained in the analyzed source file, filled only for the
+    /// metric layout.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub snippets: HashMap<Complexity, Vec<SnippetData>>,
+    /// Functions contained in the analyzed source file, filled only for the
+    /// function layout.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<FunctionData>,
+    /// Spaces which exceed some thresholds, but whose metrics are
+    /// suppressed by a comment.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<SuppressedData>,
+    /// Suppression comments which do not suppress any metric.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unused_suppressions: Vec<SuppressionData>,
 }
 
 impl Snippets {
@@ -97,142 +462,1237 @@ impl Snippets {
             source_path,
             language,
             snippets: HashMap::with_capacity(capacity),
+            functions: Vec::new(),
+            suppressed: Vec::new(),
+            unused_suppressions: Vec::new(),
         }
     }
+
+    // Whether the source file does not contain anything to report.
+    fn is_empty(&self) -> bool {
+        !self.is_complex() && self.unused_suppressions.is_empty()
+    }
+
+    // Whether the source file contains complex code, also when it is
+    // suppressed. Unused suppressions are reported, but they do not make a
+    // file complex.
+    pub(crate) fn is_complex(&self) -> bool {
+        !self.snippets.is_empty() || !self.functions.is_empty() || !self.suppressed.is_empty()
+    }
 }
 
-#[inline(always)]
-fn save_snippets(
-    complexity_type: Complexity,
-    complexity: usize,
-    start_line: usize,
-    end_line: usize,
+// Every analyzed space of a source file, recorded apart from its snippets
+// for the output formats which report also the spaces below the thresholds.
+#[derive(Debug)]
+pub(crate) struct FileSpaces {
+    pub(crate) source_path: PathBuf,
+    pub(crate) language: Language,
+    pub(crate) spaces: Vec<SpaceData>,
+}
+
+// Methods breakdown of a class space.
+fn methods_data(space: &Space) -> Vec<MethodData> {
+    methods(space.space).map(MethodData::new).collect()
+}
+
+// Value of each component of a composite score.
+fn components_data(space: &Space, options: &MetricsOptions) -> Vec<ComponentData> {
+    options
+        .composite
+        .as_ref()
+        .map_or_else(Vec::new, |composite| {
+            composite
+                .metrics()
+                .iter()
+                .map(|metric| ComponentData {
+                    metric: *metric,
+                    value: metric.compute(space.space, space.syntax, options),
+                })
+                .collect()
+        })
+}
+
+// Snippets which can be nested in the snippet of their parent space.
+trait Nest: Sized {
+    fn location(&self) -> &SpaceLocation;
+
+    fn details(&mut self) -> &mut SpaceDetails<Self>;
+
+    fn nest(&mut self, mut children: Vec<Self>) {
+        let parent_start = self.location().start_line;
+        for child in children.iter_mut() {
+            let location = child.location();
+            // Lines of a child relative to the start line of its parent.
+            let relative_lines = RelativeLinesData {
+                start_line: location.start_line + 1 - parent_start,
+                end_line: location.end_line + 1 - parent_start,
+            };
+            child.details().set_relative_lines(relative_lines);
+        }
+        self.details().children = children;
+    }
+}
+
+impl Nest for SnippetData {
+    fn location(&self) -> &SpaceLocation {
+        &self.location
+    }
+
+    fn details(&mut self) -> &mut SpaceDetails<Self> {
+        &mut self.details
+    }
+}
+
+impl Nest for FunctionData {
+    fn location(&self) -> &SpaceLocation {
+        &self.location
+    }
+
+    fn details(&mut self) -> &mut SpaceDetails<Self> {
+        &mut self.details
+    }
+}
+
+// Arranges the snippets saved after the one at `index`, which are the
+// snippets of the subspaces of its space, according to a hierarchy.
+fn arrange<T: Nest>(snippets: &mut Vec<T>, index: usize, hierarchy: Hierarchy) {
+    if snippets.len() <= index + 1 {
+        return;
+    }
+    match hierarchy {
+        Hierarchy::Flat => {}
+        Hierarchy::Nested => {
+            let children = snippets.split_off(index + 1);
+            snippets[index].nest(children);
+        }
+        Hierarchy::Leaves => {
+            snippets.remove(index);
+        }
+        Hierarchy::Outermost => snippets.truncate(index + 1),
+    }
+}
+
+// Position of a saved snippet.
+enum Position {
+    Snippet(Complexity, usize),
+    Function(usize),
+}
+
+// Text of a space together with its context.
+#[derive(Clone, Default)]
+struct Text {
     text: String,
-    snippets: &mut HashMap<Complexity, Vec<SnippetData>>,
-) {
-    // Create snippet data.
-    let snippet_data = SnippetData {
-        complexity,
-        start_line,
-        end_line,
-        text,
-    };
-    // Save snippet data.
-    snippets
-        .entry(complexity_type)
-        .or_insert_with(Vec::new)
-        .push(snippet_data);
-}
-
-fn obtain_snippets_single_space(
-    space: &FuncSpace,
-    source_file: &str,
-    complexity_thresholds: Vec<(Complexity, usize)>,
-    snippets: &mut HashMap<Complexity, Vec<SnippetData>>,
-) {
-    complexity_thresholds
-        .iter()
-        .for_each(|(complexity, threshold)| {
-            if let Some(complexity_value) = complexity.value(space, *threshold) {
-                save_snippets(
-                    *complexity,
-                    complexity_value,
-                    space.start_line,
-                    space.end_line,
-                    source_file.to_owned(),
-                    snippets,
+    leading_context: String,
+    trailing_context: String,
+    elided: Option<ElisionData>,
+}
+
+// Extracts the snippets of a single source file.
+struct Extractor<'a> {
+    lines: Vec<&'a str>,
+    complexities: Vec<Complexity>,
+    metrics: &'a MetricsOptions,
+    options: &'a SnippetsOptions,
+    // Suppression comments of the source file, together with whether they
+    // suppress some metrics.
+    suppressions: Vec<(SuppressionData, bool)>,
+    snippets: Snippets,
+    spaces: Vec<SpaceData>,
+}
+
+impl<'a> Extractor<'a> {
+    // Saves a space which exceeds the thresholds of some metrics, returning
+    // the positions of its snippets.
+    fn save(&mut self, space: &Space, exceeded: Vec<(Complexity, f64)>) -> Vec<Position> {
+        let exceeded = self.suppress(space, exceeded);
+        if exceeded.is_empty() {
+            return Vec::new();
+        }
+        let text = self.text(space);
+        match self.options.layout {
+            Layout::Metric => exceeded
+                .into_iter()
+                .map(|(complexity_type, complexity)| {
+                    let snippets = self.snippets.snippets.entry(complexity_type).or_default();
+                    let position = Position::Snippet(complexity_type, snippets.len());
+                    self.save_snippet(complexity_type, complexity, space, text.clone());
+                    position
+                })
+                .collect(),
+            Layout::Function => {
+                let position = Position::Function(self.snippets.functions.len());
+                self.save_function(space, text, exceeded);
+                vec![position]
+            }
+        }
+    }
+
+    // Saves the metrics suppressed by a comment as suppressed snippets,
+    // returning the other metrics.
+    fn suppress(
+        &mut self,
+        space: &Space,
+        exceeded: Vec<(Complexity, f64)>,
+    ) -> Vec<(Complexity, f64)> {
+        if space.syntax.suppressions.is_empty() {
+            return exceeded;
+        }
+        exceeded
+            .into_iter()
+            .filter(|(complexity_type, complexity)| {
+                let Some(&index) = space.syntax.suppressions.iter().find(|index| {
+                    self.suppressions
+                        .get(**index)
+                        .is_some_and(|(suppression, _)| suppression.suppresses(*complexity_type))
+                }) else {
+                    return true;
+                };
+                let suppressed_data = SuppressedData {
+                    location: self.location(space),
+                    metric: *complexity_type,
+                    complexity: *complexity,
+                    suppression: self.suppressions[index].0.clone(),
+                };
+                self.suppressions[index].1 = true;
+                self.snippets.suppressed.push(suppressed_data);
+                false
+            })
+            .collect()
+    }
+
+    // Suppressions which do not suppress any of the analyzed metrics.
+    // Malformed suppressions are reported apart.
+    fn unused_suppressions(&mut self) -> Vec<SuppressionData> {
+        let complexities = &self.complexities;
+        self.suppressions
+            .drain(..)
+            .filter(|(suppression, used)| {
+                !used
+                    && suppression.error.is_none()
+                    && (suppression.metrics.is_empty()
+                        || suppression
+                            .metrics
+                            .iter()
+                            .any(|metric| complexities.contains(metric)))
+            })
+            .map(|(suppression, _)| suppression)
+            .collect()
+    }
+
+    // Arranges the snippets of the subspaces of a saved space.
+    fn arrange(&mut self, positions: Vec<Position>) {
+        let hierarchy = self.options.hierarchy;
+        for position in positions {
+            match position {
+                Position::Snippet(complexity, index) => {
+                    if let Some(snippets) = self.snippets.snippets.get_mut(&complexity) {
+                        arrange(snippets, index, hierarchy);
+                    }
+                }
+                Position::Function(index) => {
+                    arrange(&mut self.snippets.functions, index, hierarchy);
+                }
+            }
+        }
+    }
+
+    // Retrieves the text of a space, eliding its middle lines when it is
+    // longer than the maximum number of lines.
+    fn text(&self, space: &Space) -> Text {
+        if !self.options.text {
+            return Text::default();
+        }
+        let start = space
+            .space
+            .start_line
+            .saturating_sub(1)
+            .min(self.lines.len());
+        let end = space.space.end_line.clamp(start, self.lines.len());
+        let lines = &self.lines[start..end];
+
+        let (text, elided) = match self.options.max_lines {
+            Some(max_lines) if lines.len() > max_lines => {
+                let head = max_lines.div_ceil(2);
+                let tail = max_lines - head;
+                let elided = ElisionData {
+                    start_line: start + head + 1,
+                    end_line: end - tail,
+                };
+                let marker = format!(
+                    "... {} lines elided ...",
+                    elided.end_line + 1 - elided.start_line
                 );
+                let text = lines[..head]
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(marker.as_str()))
+                    .chain(lines[lines.len() - tail..].iter().copied())
+                    .collect::<Vec<&str>>()
+                    .join("\n");
+                (text, Some(elided))
             }
-        });
-}
+            _ => (lines.join("\n"), None),
+        };
 
-fn obtain_snippets(
-    spaces: &[FuncSpace],
-    source_file: &str,
-    complexity_thresholds: Vec<(Complexity, usize)>,
-    snippets: &mut HashMap<Complexity, Vec<SnippetData>>,
-) {
-    // Iter over spaces.
-    for space in spaces {
-        let complexity_thresholds = complexity_thresholds
+        let context = self.options.context_lines;
+        Text {
+            text,
+            leading_context: self.lines[start.saturating_sub(context)..start].join("\n"),
+            trailing_context: self.lines[end..(end + context).min(self.lines.len())].join("\n"),
+            elided,
+        }
+    }
+
+    fn location(&self, space: &Space) -> SpaceLocation {
+        SpaceLocation {
+            name: space.name().to_string(),
+            kind: space.kind,
+            qualified_name: space.qualified_name.clone(),
+            start_line: space.space.start_line,
+            end_line: space.space.end_line,
+            start_column: space.syntax.start_column,
+            end_column: space.syntax.end_column,
+            start_byte: space.syntax.start_byte,
+            end_byte: space.syntax.end_byte,
+            fingerprint: self.fingerprint(space),
+        }
+    }
+
+    // Hashes the relative source path, the qualified name and the code of
+    // a space. Whitespace and comments are ignored, so a space keeps its
+    // fingerprint when it is moved within a file or reformatted, and the
+    // fingerprint does not depend on where the code is checked out.
+    fn fingerprint(&self, space: &Space) -> String {
+        let mut hasher = Sha256::new();
+        let path = relative_path(&self.snippets.source_path, &self.options.root);
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(space.qualified_name.as_bytes());
+        hasher.update([0]);
+        if let Some(body_hash) = space.syntax.body_hash {
+            hasher.update(body_hash);
+        } else {
+            // Without a syntax tree, only whitespace can be ignored.
+            self.lines
+                .iter()
+                .skip(space.space.start_line.saturating_sub(1))
+                .take(space.space.end_line + 1 - space.space.start_line)
+                .flat_map(|line| line.split_whitespace())
+                .for_each(|word| hasher.update(word.as_bytes()));
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    // Increments of the metrics selected by a predicate, when the explain
+    // mode is enabled.
+    fn explanation(
+        &self,
+        space: &Space,
+        predicate: impl Fn(Complexity) -> bool,
+    ) -> Vec<IncrementData> {
+        if !self.options.explain {
+            return Vec::new();
+        }
+        space
+            .syntax
+            .increments
+            .iter()
+            .filter(|increment| predicate(increment.metric))
+            .cloned()
+            .collect()
+    }
+
+    fn save_snippet(
+        &mut self,
+        complexity_type: Complexity,
+        complexity: f64,
+        space: &Space,
+        text: Text,
+    ) {
+        // Attach the methods breakdown to class snippets.
+        let methods = if complexity_type.is_class_metric() {
+            methods_data(space)
+        } else {
+            Vec::new()
+        };
+        // Attach the value of each component to composite snippets.
+        let components = if complexity_type == Complexity::Composite {
+            components_data(space, self.metrics)
+        } else {
+            Vec::new()
+        };
+        // Attach the line of the deepest block to nesting snippets.
+        let nesting = (complexity_type == Complexity::Nesting).then_some(space.syntax.nesting);
+        let explanation = self.explanation(space, |complexity| complexity == complexity_type);
+        // Create snippet data.
+        let snippet_data = SnippetData {
+            complexity,
+            location: self.location(space),
+            details: SpaceDetails {
+                methods,
+                components,
+                nesting,
+                explanation,
+                ..SpaceDetails::new(text)
+            },
+        };
+        // Save snippet data.
+        self.snippets
+            .snippets
+            .entry(complexity_type)
+            .or_default()
+            .push(snippet_data);
+    }
+
+    fn save_function(&mut self, space: &Space, text: Text, exceeded: Vec<(Complexity, f64)>) {
+        // Compute every metric, even the ones which do not exceed their
+        // thresholds.
+        let metrics = self
+            .complexities
+            .iter()
+            .filter(|complexity| complexity.applies_to(space.space.kind))
+            .map(|complexity| {
+                (
+                    *complexity,
+                    complexity.compute(space.space, space.syntax, self.metrics),
+                )
+            })
+            .filter(|(_, value)| !value.is_nan())
+            .collect();
+        let has = |predicate: fn(&Complexity) -> bool| self.complexities.iter().any(predicate);
+        let function_data = FunctionData {
+            location: self.location(space),
+            metrics,
+            exceeded: exceeded
+                .into_iter()
+                .map(|(complexity, _)| complexity)
+                .collect(),
+            details: SpaceDetails {
+                methods: if has(Complexity::is_class_metric)
+                    && Complexity::Wmc.applies_to(space.space.kind)
+                {
+                    methods_data(space)
+                } else {
+                    Vec::new()
+                },
+                components: if has(|c| *c == Complexity::Composite) {
+                    components_data(space, self.metrics)
+                } else {
+                    Vec::new()
+                },
+                nesting: has(|c| *c == Complexity::Nesting).then_some(space.syntax.nesting),
+                explanation: self
+                    .explanation(space, |complexity| self.complexities.contains(&complexity)),
+                ..SpaceDetails::new(text)
+            },
+        };
+        self.snippets.functions.push(function_data);
+    }
+
+    // Records an analyzed space.
+    fn record(&mut self, space: &Space) {
+        if !self.options.spaces || !self.options.filter.is_eligible(space) {
+            return;
+        }
+        // The metrics with a threshold are always computed, so the formats
+        // can check every space against the thresholds.
+        let complexities = if self.options.all_metrics {
+            Complexity::all()
+        } else {
+            &self.complexities
+        };
+        let metrics = complexities
+            .iter()
+            .filter(|complexity| complexity.applies_to(space.space.kind))
+            .map(|complexity| {
+                (
+                    *complexity,
+                    complexity.compute(space.space, space.syntax, self.metrics),
+                )
+            })
+            // Undefined values, e.g. the composite metric without a formula
+            // or the comment ratio of a short space, are not recorded.
+            .filter(|(_, value)| !value.is_nan())
+            .collect();
+        let space_data = SpaceData {
+            location: self.location(space),
+            metrics,
+        };
+        self.spaces.push(space_data);
+    }
+
+    fn obtain_snippets_single_space(
+        &mut self,
+        space: &Space,
+        complexity_thresholds: Vec<(Complexity, f64)>,
+    ) {
+        self.record(space);
+        let exceeded = complexity_thresholds
             .iter()
             .filter_map(|(complexity, threshold)| {
-                complexity.value(space, *threshold).map(|complexity_value| {
-                    if complexity_value > *threshold {
-                        // Get code snippet from source code.
-                        let str_lines: Vec<&str> = source_file
-                            .lines()
-                            .skip(space.start_line.saturating_sub(1))
-                            .take((space.end_line - space.start_line) + 1)
-                            .collect();
-                        save_snippets(
-                            *complexity,
-                            complexity_value,
-                            space.start_line,
-                            space.end_line,
-                            str_lines.join("\n"),
-                            snippets,
-                        );
-                    }
-                    (*complexity, *threshold)
-                })
+                complexity
+                    .value(space.space, space.syntax, *threshold, self.metrics)
+                    .filter(|complexity_value| {
+                        complexity.applies_to(space.space.kind)
+                            && complexity.exceeds(*complexity_value, *threshold)
+                    })
+                    .map(|complexity_value| (*complexity, complexity_value))
             })
-            .collect::<Vec<(Complexity, usize)>>();
+            .collect::<Vec<(Complexity, f64)>>();
+
+        if !exceeded.is_empty() && self.options.filter.is_eligible(space) {
+            self.save(space, exceeded);
+        }
+    }
+
+    fn obtain_snippets(
+        &mut self,
+        spaces: impl Iterator<Item = Space<'a>>,
+        complexity_thresholds: Vec<(Complexity, f64)>,
+    ) {
+        // Iter over spaces.
+        for space in spaces {
+            if self.options.filter.excludes(&space) {
+                continue;
+            }
+            self.record(&space);
+            let mut exceeded = Vec::new();
+            let complexity_thresholds = complexity_thresholds
+                .iter()
+                .filter_map(|(complexity, threshold)| {
+                    complexity
+                        .value(space.space, space.syntax, *threshold, self.metrics)
+                        .map(|complexity_value| {
+                            if complexity.applies_to(space.space.kind)
+                                && complexity.exceeds(complexity_value, *threshold)
+                            {
+                                exceeded.push((*complexity, complexity_value));
+                            }
+                            (*complexity, *threshold)
+                        })
+                })
+                .collect::<Vec<(Complexity, f64)>>();
+
+            let positions = if exceeded.is_empty() || !self.options.filter.is_eligible(&space) {
+                Vec::new()
+            } else {
+                self.save(&space, exceeded)
+            };
 
-        // Obtain snippets from subspaces which have high complexities values.
-        if !complexity_thresholds.is_empty() {
-            obtain_snippets(&space.spaces, source_file, complexity_thresholds, snippets);
+            // Obtain snippets from subspaces which have high complexities values.
+            if !complexity_thresholds.is_empty() || self.options.spaces {
+                self.obtain_snippets(space.subspaces(), complexity_thresholds);
+            }
+
+            // Snippets of the subspaces are saved after the snippets of
+            // their space.
+            self.arrange(positions);
         }
     }
 }
 
+// A source file analyzed by rust-code-analysis and by the syntax tree
+// visitor, which computes the metrics not provided by rust-code-analysis.
+#[derive(Debug)]
+pub(crate) struct AnalyzedFile {
+    pub(crate) source_path: PathBuf,
+    pub(crate) source_file: String,
+    pub(crate) language: LANG,
+    pub(crate) space: FuncSpace,
+    pub(crate) syntax: SpaceSyntax,
+    pub(crate) suppressions: Vec<SuppressionData>,
+}
+
+impl AnalyzedFile {
+    pub(crate) fn new(
+        source_path: PathBuf,
+        source_file: String,
+        language: LANG,
+        space: FuncSpace,
+    ) -> Self {
+        let (syntax, suppressions) = parse(language, &source_file);
+        Self {
+            source_path,
+            source_file,
+            language,
+            space,
+            syntax,
+            suppressions,
+        }
+    }
+}
+
+// Extracts the snippets of a source file, together with its analyzed spaces
+// when they have to be recorded.
 pub(crate) fn get_code_snippets(
-    space: &FuncSpace,
-    language: Language,
-    source_path: PathBuf,
-    source_file: &str,
-    complexities: &[Complexity],
-    thresholds: &[usize],
-) -> Option<Snippets> {
+    file: &AnalyzedFile,
+    complexity_thresholds: &[(Complexity, f64)],
+    metrics: &MetricsOptions,
+    options: &SnippetsOptions,
+) -> (Option<Snippets>, Option<FileSpaces>) {
+    let AnalyzedFile {
+        source_path,
+        source_file,
+        language,
+        space,
+        syntax,
+        suppressions,
+    } = file;
+    let unit = Space::unit(space, syntax);
+
     // Delete complexity metrics which are below a specified threshold.
-    let complexity_thresholds = complexities
+    let active_thresholds = complexity_thresholds
         .iter()
-        .zip(thresholds)
         .filter_map(|(complexity, threshold)| {
             complexity
-                .value(space, *threshold)
+                .value(space, syntax, *threshold, metrics)
                 .map(|_| (*complexity, *threshold))
         })
-        .collect::<Vec<(Complexity, usize)>>();
+        .collect::<Vec<(Complexity, f64)>>();
 
-    // Do not extract snippets when the code has lower complexities values.
-    if complexity_thresholds.is_empty() {
-        return None;
+    // Do not extract snippets when the code has lower complexities values,
+    // unless some suppressions have to be reported as unused or every space
+    // has to be recorded.
+    if active_thresholds.is_empty() && suppressions.is_empty() && !options.spaces {
+        return (None, None);
     }
 
     // Define structure for snippets.
-    let mut metrics_snippets = Snippets::new(source_path, language, complexity_thresholds.len());
+    let mut extractor = Extractor {
+        lines: source_file.lines().collect(),
+        complexities: complexity_thresholds
+            .iter()
+            .map(|(complexity, _)| *complexity)
+            .collect(),
+        metrics,
+        options,
+        suppressions: suppressions
+            .iter()
+            .map(|suppression| (suppression.clone(), false))
+            .collect(),
+        snippets: Snippets::new(
+            source_path.clone(),
+            (*language).into(),
+            active_thresholds.len(),
+        ),
+        spaces: Vec::new(),
+    };
 
     // If there is only one space in a file, save the entire source file for
-    // each complexity that overcomes a threshold.
+    // each complexity that overcomes a threshold, unless it is too long.
     if space.spaces.is_empty() {
-        // Snippets are the entire source file when there is only one space.
-        obtain_snippets_single_space(
-            space,
-            source_file,
-            complexity_thresholds,
-            &mut metrics_snippets.snippets,
-        );
+        // Snippets are the unit space when there is only one space.
+        extractor.obtain_snippets_single_space(&unit, active_thresholds);
     } else {
         // Obtain snippets from the source code using the complexity metrics
         // computed by rust-code-analysis
-        obtain_snippets(
-            &space.spaces,
-            source_file,
-            complexity_thresholds,
-            &mut metrics_snippets.snippets,
+        extractor.obtain_snippets(unit.subspaces(), active_thresholds);
+    }
+    extractor.snippets.unused_suppressions = extractor.unused_suppressions();
+
+    let spaces = options.spaces.then(|| FileSpaces {
+        source_path: source_path.clone(),
+        language: (*language).into(),
+        spaces: extractor.spaces,
+    });
+    // Metrics which visit every subspace might not produce any snippet.
+    let snippets = (!extractor.snippets.is_empty()).then_some(extractor.snippets);
+    (snippets, spaces)
+}
+
+fn collect_spaces_values<'a>(
+    spaces: impl Iterator<Item = Space<'a>>,
+    complexities: &[Complexity],
+    options: &MetricsOptions,
+    filter: &SpaceFilter,
+    values: &mut [Vec<f64>],
+) {
+    for space in spaces {
+        if filter.excludes(&space) {
+            continue;
+        }
+        collect_space_values(&space, complexities, options, filter, values);
+        collect_spaces_values(space.subspaces(), complexities, options, filter, values);
+    }
+}
+
+#[inline(always)]
+fn collect_space_values(
+    space: &Space,
+    complexities: &[Complexity],
+    options: &MetricsOptions,
+    filter: &SpaceFilter,
+    values: &mut [Vec<f64>],
+) {
+    // Spaces which cannot be extracted are not part of the distribution.
+    if !filter.is_eligible(space) {
+        return;
+    }
+    complexities
+        .iter()
+        .zip(values.iter_mut())
+        .filter(|(complexity, _)| complexity.applies_to(space.space.kind))
+        .for_each(|(complexity, values)| {
+            let value = complexity.compute(space.space, space.syntax, options);
+            // Undefined values, e.g. the Halstead metrics of an empty space,
+            // are not part of the distribution.
+            if value.is_finite() {
+                values.push(value);
+            }
+        });
+}
+
+// Collects the values of some metrics for each space which might be
+// extracted as a snippet.
+pub(crate) fn get_metrics_values(
+    file: &AnalyzedFile,
+    complexities: &[Complexity],
+    options: &MetricsOptions,
+    filter: &SpaceFilter,
+    values: &mut [Vec<f64>],
+) {
+    let unit = Space::unit(&file.space, &file.syntax);
+
+    // When there is only one space in a file, the snippet is the entire
+    // source file.
+    if file.space.spaces.is_empty() {
+        collect_space_values(&unit, complexities, options, filter, values);
+    } else {
+        collect_spaces_values(unit.subspaces(), complexities, options, filter, values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::str::FromStr;
+
+    use rust_code_analysis::get_function_spaces;
+
+    use super::*;
+    use crate::composite::Composite;
+    use crate::metrics::MiVariant;
+
+    const SOURCE: &str = "fn simple() {}
+
+fn branches(x: i32) -> i32 {
+    if x > 0 {
+        if x > 10 {
+            return 10;
+        }
+        return 1;
+    }
+    0
+}
+";
+
+    fn options() -> SnippetsOptions {
+        SnippetsOptions {
+            layout: Layout::Metric,
+            hierarchy: Hierarchy::Flat,
+            filter: SpaceFilter::default(),
+            context_lines: 0,
+            max_lines: None,
+            text: true,
+            explain: false,
+            spaces: false,
+            all_metrics: false,
+            root: PathBuf::new(),
+        }
+    }
+
+    // Extracts the snippets of a Rust source file.
+    fn extract(
+        source: &str,
+        complexity_thresholds: &[(Complexity, f64)],
+        composite: Option<&str>,
+        options: &SnippetsOptions,
+    ) -> Option<Snippets> {
+        let space = get_function_spaces(
+            &LANG::Rust,
+            source.as_bytes().to_vec(),
+            Path::new("test.rs"),
+            None,
+        )
+        .unwrap();
+        let metrics = MetricsOptions {
+            mi_variant: MiVariant::VisualStudio,
+            composite: composite.map(|formula| Composite::from_str(formula).unwrap()),
+        };
+        let file = AnalyzedFile::new(
+            PathBuf::from("test.rs"),
+            source.to_string(),
+            LANG::Rust,
+            space,
         );
+        get_code_snippets(&file, complexity_thresholds, &metrics, options).0
+    }
+
+    fn snippets(
+        complexity_thresholds: &[(Complexity, f64)],
+        composite: Option<&str>,
+        options: &SnippetsOptions,
+    ) -> Option<Snippets> {
+        extract(SOURCE, complexity_thresholds, composite, options)
     }
 
-    Some(metrics_snippets)
+    #[test]
+    fn composite_components() {
+        let snippets = snippets(
+            &[(Complexity::Composite, 5.)],
+            Some("cyclomatic + 2 * nexits"),
+            &options(),
+        )
+        .unwrap();
+        let composite = snippets.snippets.get(&Complexity::Composite).unwrap();
+        let components: Vec<(Complexity, f64)> = composite[0]
+            .details
+            .components
+            .iter()
+            .map(|component| (component.metric, component.value))
+            .collect();
+
+        assert_eq!(composite.len(), 1);
+        assert_eq!(composite[0].location.name, "branches");
+        assert_eq!(composite[0].complexity, 9.);
+        assert_eq!(
+            components,
+            [(Complexity::Cyclomatic, 3.), (Complexity::Nexits, 3.)]
+        );
+    }
+
+    #[test]
+    fn serialized_values() {
+        let mut options = options();
+        options.layout = Layout::Function;
+        let snippets = snippets(
+            &[(Complexity::Cyclomatic, 1.), (Complexity::HalsteadBugs, 0.)],
+            None,
+            &options,
+        )
+        .unwrap();
+        let function = snippets
+            .functions
+            .iter()
+            .find(|function| function.location.name == "branches")
+            .unwrap();
+        let function = serde_json::to_value(function).unwrap();
+
+        // Integral values are integers, the other values are floats.
+        assert_eq!(function["metrics"]["Cyclomatic"], serde_json::json!(3));
+        assert!(function["metrics"]["HalsteadBugs"].is_f64());
+    }
+
+    #[test]
+    fn positions_and_text() {
+        let mut options = options();
+        options.context_lines = 2;
+        options.max_lines = Some(4);
+        let text = snippets(&[(Complexity::Cyclomatic, 1.)], None, &options).unwrap();
+        let cyclomatic = text.snippets.get(&Complexity::Cyclomatic).unwrap();
+
+        assert_eq!(cyclomatic.len(), 1);
+        let snippet = &cyclomatic[0];
+        assert_eq!(
+            (snippet.location.start_line, snippet.location.end_line),
+            (3, 11)
+        );
+        assert_eq!(
+            (snippet.location.start_column, snippet.location.end_column),
+            (1, 2)
+        );
+        // Byte offsets delimit the whole function, signature included.
+        assert_eq!(
+            &SOURCE[snippet.location.start_byte..snippet.location.end_byte],
+            SOURCE.lines().skip(2).collect::<Vec<_>>().join("\n")
+        );
+        // The middle lines are replaced by a single line.
+        assert_eq!(
+            snippet.details.text,
+            "fn branches(x: i32) -> i32 {\n    if x > 0 {\n... 5 lines elided ...\n    0\n}"
+        );
+        assert_eq!(
+            snippet
+                .details
+                .elided
+                .map(|elided| (elided.start_line, elided.end_line)),
+            Some((5, 9))
+        );
+        assert_eq!(snippet.details.leading_context, "fn simple() {}\n");
+        assert_eq!(snippet.details.trailing_context, "");
+
+        // Without text, only the positions are reported.
+        options.text = false;
+        let positions = snippets(&[(Complexity::Cyclomatic, 1.)], None, &options).unwrap();
+        let snippet = &positions.snippets.get(&Complexity::Cyclomatic).unwrap()[0];
+        assert_eq!(
+            (snippet.location.start_byte, snippet.location.end_byte),
+            (16, 144)
+        );
+        assert_eq!(snippet.details.text, "");
+        assert_eq!(snippet.details.leading_context, "");
+        assert!(snippet.details.elided.is_none());
+    }
+
+    #[test]
+    fn deepest_nesting() {
+        let snippets = snippets(&[(Complexity::Nesting, 1.)], None, &options()).unwrap();
+        let nesting = snippets.snippets.get(&Complexity::Nesting).unwrap();
+
+        assert_eq!(nesting.len(), 1);
+        assert_eq!(nesting[0].complexity, 2.);
+        // The deepest block is the inner `if`.
+        assert_eq!(
+            nesting[0]
+                .details
+                .nesting
+                .map(|nesting| (nesting.depth, nesting.line)),
+            Some((2, 5))
+        );
+    }
+
+    #[test]
+    fn function_explanation() {
+        let mut options = options();
+        options.layout = Layout::Function;
+        options.explain = true;
+        let snippets = snippets(
+            &[(Complexity::Cyclomatic, 1.), (Complexity::Cognitive, 1.)],
+            None,
+            &options,
+        )
+        .unwrap();
+        let increments = snippets.functions[0]
+            .details
+            .explanation
+            .iter()
+            .map(|increment| {
+                (
+                    increment.metric,
+                    increment.line,
+                    increment.increment,
+                    increment.reason.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert!(snippets.snippets.is_empty());
+        assert_eq!(snippets.functions.len(), 1);
+        assert_eq!(
+            snippets.functions[0].exceeded,
+            [Complexity::Cyclomatic, Complexity::Cognitive]
+        );
+        // The increments of each metric sum up to its value, 3.
+        assert_eq!(
+            increments,
+            [
+                (Complexity::Cyclomatic, 3, 1, "entry point"),
+                (Complexity::Cognitive, 4, 1, "if"),
+                (Complexity::Cyclomatic, 4, 1, "if"),
+                (Complexity::Cognitive, 5, 2, "if, nesting +1"),
+                (Complexity::Cyclomatic, 5, 1, "if"),
+            ]
+        );
+    }
+
+    const NESTED: &str = "impl Parser {
+    fn parse(&self, input: &str) -> usize {
+        let count = |c: char| {
+            if c == 'a' {
+                1
+            } else {
+                0
+            }
+        };
+        input.chars().map(count).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn parse_test() {
+        let x = 1;
+        let y = 2;
+    }
+}
+";
+
+    // Location of each snippet of a metric.
+    fn locations(snippets: &Snippets, complexity: Complexity) -> Vec<(&str, SpaceKind)> {
+        snippets.snippets[&complexity]
+            .iter()
+            .map(|snippet| {
+                (
+                    snippet.location.qualified_name.as_str(),
+                    snippet.location.kind,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn qualified_names() {
+        let snippets = extract(NESTED, &[(Complexity::Sloc, 3.)], None, &options()).unwrap();
+
+        // Modules are not spaces, so they are not part of the names.
+        assert_eq!(
+            locations(&snippets, Complexity::Sloc),
+            [
+                ("impl Parser", SpaceKind::Impl),
+                ("impl Parser > fn parse", SpaceKind::Function),
+                ("impl Parser > fn parse > closure", SpaceKind::Closure),
+                ("fn parse_test", SpaceKind::Function),
+            ]
+        );
+    }
+
+    #[test]
+    fn space_filters() {
+        let mut options = options();
+        options.filter.kinds = vec![SpaceKind::Closure];
+        let snippets = extract(NESTED, &[(Complexity::Sloc, 3.)], None, &options).unwrap();
+
+        // Closures are found even if their functions are not reported.
+        assert_eq!(
+            locations(&snippets, Complexity::Sloc),
+            [("impl Parser > fn parse > closure", SpaceKind::Closure)]
+        );
+
+        let mut options = self::options();
+        options.filter.exclude_tests = true;
+        let snippets = extract(NESTED, &[(Complexity::Sloc, 3.)], None, &options).unwrap();
+
+        // Test functions are contained in `#[cfg(test)]` modules.
+        assert_eq!(
+            locations(&snippets, Complexity::Sloc),
+            [
+                ("impl Parser", SpaceKind::Impl),
+                ("impl Parser > fn parse", SpaceKind::Function),
+                ("impl Parser > fn parse > closure", SpaceKind::Closure),
+            ]
+        );
+    }
+
+    #[test]
+    fn hierarchies() {
+        let sloc = |hierarchy| {
+            let mut options = options();
+            options.hierarchy = hierarchy;
+            extract(NESTED, &[(Complexity::Sloc, 3.)], None, &options)
+                .unwrap()
+                .snippets
+                .remove(&Complexity::Sloc)
+                .unwrap()
+        };
+        let names = |snippets: &[SnippetData]| {
+            snippets
+                .iter()
+                .map(|snippet| snippet.location.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&sloc(Hierarchy::Flat)),
+            ["Parser", "parse", "<anonymous>", "parse_test"]
+        );
+        assert_eq!(
+            names(&sloc(Hierarchy::Leaves)),
+            ["<anonymous>", "parse_test"]
+        );
+        assert_eq!(names(&sloc(Hierarchy::Outermost)), ["Parser", "parse_test"]);
+
+        // Each snippet is nested in the snippet of its parent space, with
+        // lines relative to it and without its text, which is already
+        // contained in the parent.
+        let nested = sloc(Hierarchy::Nested);
+        let relative_lines = |snippet: &SnippetData| {
+            snippet
+                .details
+                .relative_lines
+                .map(|lines| (lines.start_line, lines.end_line))
+        };
+        assert_eq!(names(&nested), ["Parser", "parse_test"]);
+        assert!(nested[1].details.children.is_empty());
+        let function = &nested[0].details.children[0];
+        assert_eq!(names(&nested[0].details.children), ["parse"]);
+        assert_eq!(relative_lines(function), Some((2, 11)));
+        assert!(function.details.text.is_empty());
+        let closure = &function.details.children[0];
+        assert_eq!(names(&function.details.children), ["<anonymous>"]);
+        assert_eq!(relative_lines(closure), Some((2, 8)));
+        assert!(closure.details.text.is_empty());
+    }
+
+    #[test]
+    fn fingerprints() {
+        let fingerprints = |source: &str| {
+            let snippets = extract(source, &[(Complexity::Sloc, 3.)], None, &options()).unwrap();
+            snippets.snippets[&Complexity::Sloc]
+                .iter()
+                .map(|snippet| snippet.location.fingerprint.clone())
+                .collect::<Vec<_>>()
+        };
+        let first = fingerprints(NESTED);
+
+        // Fingerprints identify each snippet and do not change across runs.
+        let mut unique = first.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), first.len());
+        assert_eq!(fingerprints(NESTED), first);
+
+        // Moving the code does not change the fingerprints, while changing
+        // the code of a space changes only its fingerprint.
+        assert_eq!(fingerprints(&format!("// Parser.\n\n{NESTED}")), first);
+        let changed = fingerprints(&NESTED.replace("let y = 2;", "let y = 3;"));
+        assert_eq!(changed[..3], first[..3]);
+        assert_ne!(changed[3], first[3]);
+    }
+
+    #[test]
+    fn relative_paths() {
+        // Paths are relative to the root, so the fingerprints do not depend
+        // on where the code is checked out.
+        let root = Path::new("/home/user/project");
+        assert_eq!(
+            relative_path(&root.join("src").join("main.rs"), root),
+            "src/main.rs"
+        );
+        assert_eq!(
+            relative_path(Path::new("/tmp/main.rs"), root),
+            "/tmp/main.rs"
+        );
+        assert_eq!(
+            relative_path(Path::new("src\\main.rs"), Path::new("")),
+            "src/main.rs"
+        );
+    }
+
+    #[test]
+    fn function_layout() {
+        let mut options = options();
+        options.layout = Layout::Function;
+        let snippets = snippets(
+            &[(Complexity::Cyclomatic, 1.), (Complexity::Cognitive, 5.)],
+            None,
+            &options,
+        )
+        .unwrap();
+
+        // Each function appears once, with the values of all the metrics,
+        // also the ones which do not exceed their thresholds.
+        assert!(snippets.snippets.is_empty());
+        assert_eq!(snippets.functions.len(), 1);
+        let function = &snippets.functions[0];
+        assert_eq!(function.location.name, "branches");
+        assert_eq!(function.exceeded, [Complexity::Cyclomatic]);
+        assert_eq!(
+            function.metrics,
+            BTreeMap::from([(Complexity::Cyclomatic, 3.), (Complexity::Cognitive, 3.)])
+        );
+    }
+
+    #[test]
+    fn class_methods() {
+        let snippets = extract(
+            "impl Counter {
+    fn add(&mut self, value: i32) {
+        if value > 0 {
+            self.count += value;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+",
+            &[(Complexity::Wmc, 2.)],
+            None,
+            &options(),
+        )
+        .unwrap();
+        let wmc = &snippets.snippets[&Complexity::Wmc];
+
+        // Class snippets carry the breakdown of their methods.
+        assert_eq!(wmc.len(), 1);
+        assert_eq!(wmc[0].location.qualified_name, "impl Counter");
+        assert_eq!(wmc[0].complexity, 3.);
+        assert_eq!(
+            wmc[0]
+                .details
+                .methods
+                .iter()
+                .map(|method| (method.name.as_str(), method.cyclomatic))
+                .collect::<Vec<_>>(),
+            [("add", 2.), ("reset", 1.)]
+        );
+    }
+
+    #[test]
+    fn suppressions() {
+        let snippets = extract(
+            "fn f(x: i32) -> i32 {
+    if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+// ccs:allow(cyclomatic, reason = \"lookup table\")
+fn g(x: i32) -> i32 {
+    if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/* ccs:allow(cyclomatic) */
+fn h() {}
+
+// ccs:allow(cyclomatc)
+fn k(x: i32) -> i32 {
+    if x > 0 {
+        1
+    } else {
+        0
+    }
+}
+",
+            &[(Complexity::Cyclomatic, 1.)],
+            None,
+            &options(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            locations(&snippets, Complexity::Cyclomatic),
+            [("fn f", SpaceKind::Function), ("fn k", SpaceKind::Function)]
+        );
+        assert_eq!(snippets.suppressed.len(), 1);
+        assert_eq!(snippets.suppressed[0].location.qualified_name, "fn g");
+        assert_eq!(
+            snippets.suppressed[0].suppression.reason.as_deref(),
+            Some("lookup table")
+        );
+        // The suppression of `h` is unused, since `h` is simple.
+        assert_eq!(snippets.unused_suppressions.len(), 1);
+        assert_eq!(snippets.unused_suppressions[0].line, 18);
+        // The misspelled suppression of `k` is malformed, so it does not
+        // suppress anything and it is not reported as unused.
+        assert!(snippets.is_complex());
+
+        // Unused suppressions are reported, but they are not complex code.
+        let snippets = extract(
+            "// ccs:allow(cyclomatic)\nfn h() {}\n",
+            &[(Complexity::Cyclomatic, 1.)],
+            None,
+            &options(),
+        )
+        .unwrap();
+        assert_eq!(snippets.unused_suppressions.len(), 1);
+        assert!(!snippets.is_complex());
+    }
 }
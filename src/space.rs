@@ -0,0 +1,123 @@
+use arg_enum_proc_macro::ArgEnum;
+use rust_code_analysis::FuncSpace;
+use serde::Serialize;
+
+use crate::syntax::SpaceSyntax;
+
+/// Kinds of spaces.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum SpaceKind {
+    /// A function or a method.
+    #[arg_enum(name = "function")]
+    Function,
+    /// An anonymous function, such as a closure or a lambda.
+    #[arg_enum(name = "closure")]
+    Closure,
+    /// An implementation block.
+    #[arg_enum(name = "impl")]
+    Impl,
+    /// A trait.
+    #[arg_enum(name = "trait")]
+    Trait,
+    /// A class or a struct.
+    #[arg_enum(name = "class")]
+    Class,
+    /// An interface.
+    #[arg_enum(name = "interface")]
+    Interface,
+    /// A namespace.
+    #[arg_enum(name = "namespace")]
+    Namespace,
+    /// A whole source file.
+    #[arg_enum(name = "unit")]
+    Unit,
+}
+
+impl SpaceKind {
+    fn new(space: &FuncSpace, syntax: &SpaceSyntax) -> Self {
+        use rust_code_analysis::SpaceKind as Kind;
+
+        match space.kind {
+            Kind::Function if syntax.closure => Self::Closure,
+            Kind::Class | Kind::Struct => Self::Class,
+            Kind::Trait => Self::Trait,
+            Kind::Impl => Self::Impl,
+            Kind::Interface => Self::Interface,
+            Kind::Namespace => Self::Namespace,
+            Kind::Unit => Self::Unit,
+            // Spaces are functions unless stated otherwise.
+            Kind::Function | Kind::Unknown => Self::Function,
+        }
+    }
+
+    // Keyword used to denote a space in a qualified name.
+    const fn keyword(&self) -> &'static str {
+        match self {
+            Self::Function => "fn",
+            Self::Closure => "closure",
+            Self::Impl => "impl",
+            Self::Trait => "trait",
+            Self::Class => "class",
+            Self::Interface => "interface",
+            Self::Namespace => "namespace",
+            Self::Unit => "unit",
+        }
+    }
+}
+
+// A space together with the data obtained by visiting its syntax tree.
+pub(crate) struct Space<'a> {
+    pub(crate) space: &'a FuncSpace,
+    pub(crate) syntax: &'a SpaceSyntax,
+    pub(crate) kind: SpaceKind,
+    // Names of the enclosing spaces and of the space itself, e.g.
+    // `impl Context > fn new`. The qualified name of a unit is its name.
+    pub(crate) qualified_name: String,
+}
+
+impl<'a> Space<'a> {
+    pub(crate) fn unit(space: &'a FuncSpace, syntax: &'a SpaceSyntax) -> Self {
+        let mut unit = Self {
+            space,
+            syntax,
+            kind: SpaceKind::Unit,
+            qualified_name: String::new(),
+        };
+        unit.qualified_name = unit.name().to_string();
+        unit
+    }
+
+    // Space name, anonymous spaces are called `<anonymous>`.
+    pub(crate) fn name(&self) -> &'a str {
+        self.space.name.as_deref().unwrap_or("<anonymous>")
+    }
+
+    // Subspaces of a space.
+    pub(crate) fn subspaces(&self) -> impl Iterator<Item = Space<'a>> + '_ {
+        self.space
+            .spaces
+            .iter()
+            .enumerate()
+            .map(move |(index, space)| {
+                let syntax = self.syntax.subspace(index, space);
+                let kind = SpaceKind::new(space, syntax);
+                let name = match (kind, space.name.as_deref()) {
+                    (SpaceKind::Closure, _) | (_, None | Some("<anonymous>")) => {
+                        kind.keyword().to_string()
+                    }
+                    (_, Some(name)) => format!("{} {name}", kind.keyword()),
+                };
+                let qualified_name = if self.kind == SpaceKind::Unit {
+                    name
+                } else {
+                    format!("{} > {name}", self.qualified_name)
+                };
+                Space {
+                    space,
+                    syntax,
+                    kind,
+                    qualified_name,
+                }
+            })
+    }
+}
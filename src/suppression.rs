@@ -0,0 +1,267 @@
+use std::str::FromStr;
+
+use rust_code_analysis::LANG;
+use serde::Serialize;
+
+use crate::metrics::Complexity;
+
+// Marker of a suppression comment.
+const MARKER: &str = "ccs:allow(";
+
+/// Suppression comment, e.g. `// ccs:allow(cognitive, reason = "parser")`,
+/// which accepts the complexity of the space below it or on its line.
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressionData {
+    /// Line of the suppression comment.
+    pub line: usize,
+    /// Suppressed metrics, all metrics when empty.
+    pub metrics: Vec<Complexity>,
+    /// Reason why the complexity is accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    // Why the suppression is malformed, e.g. a misspelled metric. A
+    // malformed suppression does not suppress any metric.
+    #[serde(skip)]
+    pub(crate) error: Option<String>,
+}
+
+impl SuppressionData {
+    // Parses the suppression contained in a comment, if any. A malformed
+    // suppression is returned together with its error, so it can be
+    // reported.
+    pub(crate) fn parse(comment: &str, line: usize) -> Option<Self> {
+        let start = comment.find(MARKER)? + MARKER.len();
+        let suppression = match parse_arguments(&comment[start..]) {
+            Ok((metrics, reason)) => Self {
+                line,
+                metrics,
+                reason,
+                error: None,
+            },
+            Err(error) => Self {
+                line,
+                metrics: Vec::new(),
+                reason: None,
+                error: Some(error),
+            },
+        };
+        Some(suppression)
+    }
+
+    // Whether a metric is suppressed.
+    pub(crate) fn suppresses(&self, complexity: Complexity) -> bool {
+        self.error.is_none() && (self.metrics.is_empty() || self.metrics.contains(&complexity))
+    }
+}
+
+// Parses the metrics and the reason of a suppression.
+fn parse_arguments(text: &str) -> Result<(Vec<Complexity>, Option<String>), String> {
+    let mut metrics = Vec::new();
+    let mut reason = None;
+    let arguments = arguments(text).ok_or("the suppression is not closed")?;
+    for argument in arguments {
+        let argument = argument.trim();
+        match argument.split_once('=') {
+            Some((key, value)) if key.trim() == "reason" => {
+                let value = value.trim();
+                reason = Some(
+                    value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                        .ok_or("the reason MUST be a quoted string")?
+                        .replace("\\\"", "\""),
+                );
+            }
+            Some(_) => return Err(format!("unknown argument `{argument}`")),
+            None => metrics.push(
+                Complexity::from_str(argument)
+                    .map_err(|_| format!("unknown metric `{argument}`"))?,
+            ),
+        }
+    }
+    Ok((metrics, reason))
+}
+
+// Splits the arguments of a suppression, up to its closing parenthesis.
+// Commas and parentheses contained in quoted strings are not separators.
+fn arguments(text: &str) -> Option<Vec<&str>> {
+    let mut arguments = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' | ')' if !quoted => {
+                let argument = &text[start..index];
+                if !argument.trim().is_empty() {
+                    arguments.push(argument);
+                }
+                if c == ')' {
+                    return Some(arguments);
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    // The suppression is not closed.
+    None
+}
+
+// Syntax of the lines which can be placed between a suppression comment and
+// its space, i.e. comments, attributes, annotations and decorators.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HeaderSyntax {
+    // Prefixes of the line comments.
+    line_comments: &'static [&'static str],
+    // Prefixes of the attributes, annotations and decorators.
+    attributes: &'static [&'static str],
+    // Whether the language has `/* */` block comments.
+    block_comments: bool,
+}
+
+impl HeaderSyntax {
+    pub(crate) const fn new(lang: LANG) -> Self {
+        match lang {
+            LANG::Rust => Self {
+                line_comments: &["//"],
+                attributes: &["#["],
+                block_comments: true,
+            },
+            LANG::Python => Self {
+                line_comments: &["#"],
+                attributes: &["@"],
+                block_comments: false,
+            },
+            LANG::Cpp | LANG::Ccomment | LANG::Preproc => Self {
+                line_comments: &["//"],
+                attributes: &["[["],
+                block_comments: true,
+            },
+            LANG::Java | LANG::Javascript | LANG::Mozjs | LANG::Typescript | LANG::Tsx => Self {
+                line_comments: &["//"],
+                attributes: &["@"],
+                block_comments: true,
+            },
+        }
+    }
+
+    // Whether some lines can be placed between a suppression comment and its
+    // space. The lines of a block comment are accepted only while it is
+    // open, so code such as `*p = 0;` is not mistaken for a comment.
+    pub(crate) fn is_header(&self, lines: &[&str]) -> bool {
+        let mut block_comment = false;
+        lines.iter().all(|line| {
+            let mut line = line.trim();
+            if block_comment {
+                let Some(end) = line.find("*/") else {
+                    return true;
+                };
+                block_comment = false;
+                line = line[end + 2..].trim_start();
+            }
+            if self.block_comments {
+                if let Some(comment) = line.strip_prefix("/*") {
+                    match comment.find("*/") {
+                        Some(end) => line = comment[end + 2..].trim_start(),
+                        None => {
+                            block_comment = true;
+                            return true;
+                        }
+                    }
+                }
+            }
+            line.is_empty()
+                || self
+                    .line_comments
+                    .iter()
+                    .chain(self.attributes)
+                    .any(|prefix| line.starts_with(prefix))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_suppressions() {
+        let suppression = SuppressionData::parse(
+            "// ccs:allow(cognitive, cyclomatic, reason = \"state machine, (sorry)\")",
+            3,
+        )
+        .unwrap();
+        assert_eq!(suppression.line, 3);
+        assert_eq!(
+            suppression.metrics,
+            [Complexity::Cognitive, Complexity::Cyclomatic]
+        );
+        assert_eq!(
+            suppression.reason.as_deref(),
+            Some("state machine, (sorry)")
+        );
+        assert!(!suppression.suppresses(Complexity::Sloc));
+
+        // Without metrics, every metric is suppressed.
+        let suppression = SuppressionData::parse("# ccs:allow()", 1).unwrap();
+        assert!(suppression.reason.is_none());
+        assert!(suppression.suppresses(Complexity::Sloc));
+
+        assert!(SuppressionData::parse("// complex on purpose", 1).is_none());
+
+        // Malformed suppressions do not suppress any metric.
+        let error = |comment| {
+            let suppression = SuppressionData::parse(comment, 1).unwrap();
+            assert!(!suppression.suppresses(Complexity::Cognitive));
+            suppression.error.unwrap()
+        };
+        assert_eq!(
+            error("/* ccs:allow(cognitve) */"),
+            "unknown metric `cognitve`"
+        );
+        assert_eq!(
+            error("// ccs:allow(cognitive"),
+            "the suppression is not closed"
+        );
+        assert_eq!(
+            error("// ccs:allow(reason = parser)"),
+            "the reason MUST be a quoted string"
+        );
+        assert_eq!(
+            error("// ccs:allow(because = \"parser\")"),
+            "unknown argument `because = \"parser\"`"
+        );
+    }
+
+    #[test]
+    fn header_lines() {
+        let header = |lang, lines: &[&str]| HeaderSyntax::new(lang).is_header(lines);
+
+        assert!(header(
+            LANG::Rust,
+            &[
+                "    #[inline]",
+                "",
+                "// Parser.",
+                "/* Long",
+                " * comment */"
+            ]
+        ));
+        assert!(header(LANG::Java, &["  @Override", "/** Docs. */"]));
+        assert!(header(LANG::Python, &["# Parser.", "@cache"]));
+        assert!(!header(LANG::Rust, &["    let x = 1;"]));
+        // Dereferences and preprocessor lines are code.
+        assert!(!header(LANG::Cpp, &["*p = 0;"]));
+        assert!(!header(LANG::Cpp, &["#define X 1"]));
+        assert!(!header(LANG::Cpp, &["/* Comment. */ *p = 0;"]));
+        assert!(!header(LANG::Rust, &["#![allow(dead_code)]"]));
+        // Only Python comments start with `#`, and no comment with `--`.
+        assert!(!header(LANG::Java, &["# x"]));
+        assert!(!header(LANG::Python, &["-- x"]));
+        assert!(!header(LANG::Cpp, &["-- x"]));
+    }
+}
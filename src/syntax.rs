@@ -0,0 +1,1031 @@
+use rust_code_analysis::{FuncSpace, LANG};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tree_sitter::{Language, Node, Parser};
+
+use crate::explain::{Explainer, IncrementData, Rules, Scope};
+use crate::suppression::{HeaderSyntax, SuppressionData};
+
+// Node kinds of a grammar analyzed by the syntax tree visitor.
+struct Kinds {
+    // Nodes which define a space, they must be the same nodes used by
+    // rust-code-analysis to compute the metrics of each space.
+    spaces: &'static [&'static str],
+    // Spaces which are anonymous functions.
+    closures: &'static [&'static str],
+    // Nodes which increase the nesting level of a block.
+    nesting: &'static [&'static str],
+    // Whether a node is test code, so all its spaces are test spaces.
+    tests: fn(&Node, &[u8]) -> bool,
+    // Members of a class-like space, for the grammars where
+    // rust-code-analysis does not count the public ones.
+    members: fn(&Node, &[u8]) -> Option<ClassMembers>,
+}
+
+const RUST: Kinds = Kinds {
+    spaces: &[
+        "source_file",
+        "function_item",
+        "impl_item",
+        "trait_item",
+        "closure_expression",
+    ],
+    closures: &["closure_expression"],
+    nesting: &[
+        "if_expression",
+        "match_expression",
+        "for_expression",
+        "while_expression",
+        "loop_expression",
+    ],
+    tests: rust_tests,
+    members: rust_members,
+};
+
+const PYTHON: Kinds = Kinds {
+    spaces: &["module", "function_definition", "class_definition"],
+    closures: &[],
+    nesting: &[
+        "if_statement",
+        "for_statement",
+        "while_statement",
+        "try_statement",
+        "with_statement",
+        "match_statement",
+    ],
+    tests: python_tests,
+    members: no_members,
+};
+
+const JAVA: Kinds = Kinds {
+    spaces: &[
+        "program",
+        "class_declaration",
+        "interface_declaration",
+        "method_declaration",
+        "constructor_declaration",
+    ],
+    closures: &[],
+    nesting: &[
+        "if_statement",
+        "for_statement",
+        "enhanced_for_statement",
+        "while_statement",
+        "do_statement",
+        "switch_expression",
+        "try_statement",
+        "try_with_resources_statement",
+        "synchronized_statement",
+    ],
+    tests: java_tests,
+    members: no_members,
+};
+
+const CPP: Kinds = Kinds {
+    spaces: &[
+        "translation_unit",
+        "function_definition",
+        "struct_specifier",
+        "class_specifier",
+        "namespace_definition",
+    ],
+    closures: &[],
+    nesting: &[
+        "if_statement",
+        "for_statement",
+        "for_range_loop",
+        "while_statement",
+        "do_statement",
+        "switch_statement",
+        "try_statement",
+    ],
+    tests: no_tests,
+    members: cpp_members,
+};
+
+const JS_CLOSURES: &[&str] = &["function", "generator_function", "arrow_function"];
+
+const JS_NESTING: &[&str] = &[
+    "if_statement",
+    "for_statement",
+    "for_in_statement",
+    "while_statement",
+    "do_statement",
+    "switch_statement",
+    "try_statement",
+    "with_statement",
+];
+
+const JAVASCRIPT: Kinds = Kinds {
+    spaces: &[
+        "program",
+        "function",
+        "class",
+        "generator_function",
+        "function_declaration",
+        "method_definition",
+        "generator_function_declaration",
+        "class_declaration",
+        "arrow_function",
+    ],
+    closures: JS_CLOSURES,
+    nesting: JS_NESTING,
+    tests: javascript_tests,
+    members: no_members,
+};
+
+const TYPESCRIPT: Kinds = Kinds {
+    spaces: &[
+        "program",
+        "function",
+        "class",
+        "generator_function",
+        "function_declaration",
+        "method_definition",
+        "generator_function_declaration",
+        "class_declaration",
+        "interface_declaration",
+        "arrow_function",
+    ],
+    closures: JS_CLOSURES,
+    nesting: JS_NESTING,
+    tests: javascript_tests,
+    members: no_members,
+};
+
+// Text of a node without whitespace.
+fn compact_text(node: &Node, source: &[u8]) -> String {
+    node.utf8_text(source)
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect()
+}
+
+// Whether a compact `cfg` predicate enables code only for tests, i.e. the
+// `test` option is one of its atoms, also within `all` and `any`, e.g.
+// `all(test,feature="x")`. Negated atoms are not considered.
+fn cfg_test(predicate: &str) -> bool {
+    let Some(arguments) = predicate
+        .strip_prefix("all(")
+        .or_else(|| predicate.strip_prefix("any("))
+        .and_then(|arguments| arguments.strip_suffix(')'))
+    else {
+        return predicate == "test";
+    };
+    // Split the arguments on the commas which are not nested in other
+    // predicates or in strings.
+    let mut depth = 0;
+    let mut string = false;
+    let mut start = 0;
+    let mut found = false;
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '"' => string = !string,
+            '(' if !string => depth += 1,
+            ')' if !string => depth -= 1,
+            ',' if !string && depth == 0 => {
+                found |= cfg_test(&arguments[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    found || cfg_test(&arguments[start..])
+}
+
+// Rust test code is marked by a `#[cfg]` attribute requiring the `test`
+// option, e.g. `#[cfg(test)]`, by `#[test]` or by the test attribute of a
+// crate, e.g. `#[tokio::test]`.
+fn rust_tests(node: &Node, source: &[u8]) -> bool {
+    if !matches!(node.kind(), "mod_item" | "function_item" | "impl_item") {
+        return false;
+    }
+    // Attributes precede the item they are applied to.
+    let mut sibling = node.prev_sibling();
+    while let Some(attribute) = sibling {
+        if attribute.kind() == "attribute_item" {
+            let text = compact_text(&attribute, source);
+            let attribute = text.trim_start_matches("#[").trim_end_matches(']');
+            let path = attribute.split('(').next().unwrap_or_default();
+            let cfg = attribute
+                .strip_prefix("cfg(")
+                .and_then(|predicate| predicate.strip_suffix(')'));
+            if cfg.is_some_and(cfg_test) || path.rsplit("::").next() == Some("test") {
+                return true;
+            }
+        } else if !attribute.is_extra() {
+            break;
+        }
+        sibling = attribute.prev_sibling();
+    }
+    false
+}
+
+// Pytest collects the `test` functions and the `Test` classes.
+fn python_tests(node: &Node, source: &[u8]) -> bool {
+    let prefix = match node.kind() {
+        "function_definition" => "test",
+        "class_definition" => "Test",
+        _ => return false,
+    };
+    node.child_by_field_name("name")
+        .and_then(|name| name.utf8_text(source).ok())
+        .is_some_and(|name| name.starts_with(prefix))
+}
+
+// Annotations of the JUnit test methods, matched by their simple name.
+const JUNIT_TESTS: &[&str] = &[
+    "Test",
+    "ParameterizedTest",
+    "RepeatedTest",
+    "TestFactory",
+    "TestTemplate",
+    "Theory",
+];
+
+// JUnit tests are annotated with `@Test`, or with annotations such as
+// `@ParameterizedTest`, also when their name is qualified.
+fn java_tests(node: &Node, source: &[u8]) -> bool {
+    if node.kind() != "method_declaration" {
+        return false;
+    }
+    node.children(&mut node.walk())
+        .filter(|child| child.kind() == "modifiers")
+        .any(|modifiers| {
+            modifiers.children(&mut modifiers.walk()).any(|annotation| {
+                matches!(annotation.kind(), "marker_annotation" | "annotation")
+                    && annotation
+                        .child_by_field_name("name")
+                        .and_then(|name| name.utf8_text(source).ok())
+                        .and_then(|name| name.rsplit('.').next())
+                        .is_some_and(|name| JUNIT_TESTS.contains(&name))
+            })
+        })
+}
+
+// JavaScript tests are the callbacks of `describe`, `it` and `test`, also
+// when they are called through a modifier such as `it.only`.
+fn javascript_tests(node: &Node, source: &[u8]) -> bool {
+    if node.kind() != "call_expression" {
+        return false;
+    }
+    let function = node.child_by_field_name("function").map(|function| {
+        if function.kind() == "member_expression" {
+            function.child_by_field_name("object").unwrap_or(function)
+        } else {
+            function
+        }
+    });
+    function
+        .and_then(|function| function.utf8_text(source).ok())
+        .is_some_and(|name| matches!(name, "describe" | "it" | "test"))
+}
+
+fn no_tests(_node: &Node, _source: &[u8]) -> bool {
+    false
+}
+
+// Items of traits and of trait implementations are as public as the trait,
+// the other Rust items are public only when marked by a plain `pub`.
+// Associated constants are the attributes of implementations and traits,
+// while methods are counted also when they are only declared by a trait.
+fn rust_members(node: &Node, source: &[u8]) -> Option<ClassMembers> {
+    if !matches!(node.kind(), "impl_item" | "trait_item") {
+        return None;
+    }
+    let trait_items = node.kind() == "trait_item" || node.child_by_field_name("trait").is_some();
+    let mut members = ClassMembers::default();
+    let Some(body) = node.child_by_field_name("body") else {
+        return Some(members);
+    };
+    let mut cursor = body.walk();
+    for item in body.named_children(&mut cursor) {
+        let mut cursor = item.walk();
+        let public = trait_items
+            || item.children(&mut cursor).any(|child| {
+                child.kind() == "visibility_modifier" && child.utf8_text(source).ok() == Some("pub")
+            });
+        match item.kind() {
+            "function_item" | "function_signature_item" => members.add_method(public),
+            "const_item" if public => members.public_attributes += 1,
+            _ => {}
+        }
+    }
+    Some(members)
+}
+
+// Whether a C++ declarator declares a function, also when the function
+// returns a pointer or a reference.
+fn is_function_declarator(declarator: &Node) -> bool {
+    match declarator.kind() {
+        "function_declarator" => true,
+        "pointer_declarator" | "reference_declarator" => declarator
+            .named_children(&mut declarator.walk())
+            .any(|child| is_function_declarator(&child)),
+        _ => false,
+    }
+}
+
+// Counts the methods and the public attributes declared by a member of a
+// C++ class. Methods are counted also when they are only declared.
+fn cpp_member(member: &Node, public: bool, members: &mut ClassMembers) {
+    match member.kind() {
+        "function_definition" => members.add_method(public),
+        // A single declaration might declare more attributes.
+        "field_declaration" | "declaration" => {
+            let mut cursor = member.walk();
+            for declarator in member.children_by_field_name("declarator", &mut cursor) {
+                if is_function_declarator(&declarator) {
+                    members.add_method(public);
+                } else if public {
+                    members.public_attributes += 1;
+                }
+            }
+        }
+        "template_declaration" => {
+            let mut cursor = member.walk();
+            for child in member.named_children(&mut cursor) {
+                cpp_member(&child, public, members);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Members of C++ classes are private by default, while members of structs
+// are public, until an access specifier changes the visibility of the
+// members which follow it. Friends are not members.
+fn cpp_members(node: &Node, source: &[u8]) -> Option<ClassMembers> {
+    let mut public = match node.kind() {
+        "class_specifier" => false,
+        "struct_specifier" => true,
+        _ => return None,
+    };
+    let mut members = ClassMembers::default();
+    let Some(body) = node.child_by_field_name("body") else {
+        return Some(members);
+    };
+    let mut cursor = body.walk();
+    for member in body.named_children(&mut cursor) {
+        if member.kind() == "access_specifier" {
+            public = compact_text(&member, source).starts_with("public");
+        } else {
+            cpp_member(&member, public, &mut members);
+        }
+    }
+    Some(members)
+}
+
+fn no_members(_node: &Node, _source: &[u8]) -> Option<ClassMembers> {
+    None
+}
+
+fn grammar(lang: LANG) -> Option<(Language, &'static Kinds, Rules)> {
+    match lang {
+        LANG::Rust => Some((tree_sitter_rust::language(), &RUST, Rules::Rust)),
+        LANG::Python => Some((tree_sitter_python::language(), &PYTHON, Rules::Python)),
+        LANG::Java => Some((tree_sitter_java::language(), &JAVA, Rules::Java)),
+        LANG::Cpp => Some((tree_sitter_mozcpp::language(), &CPP, Rules::Cpp)),
+        LANG::Javascript => Some((
+            tree_sitter_javascript::language(),
+            &JAVASCRIPT,
+            Rules::Javascript {
+                else_if: "if_statement",
+            },
+        )),
+        LANG::Mozjs => Some((
+            tree_sitter_mozjs::language(),
+            &JAVASCRIPT,
+            Rules::Javascript {
+                else_if: "else_clause",
+            },
+        )),
+        LANG::Typescript => Some((
+            tree_sitter_typescript::language_typescript(),
+            &TYPESCRIPT,
+            Rules::Javascript {
+                else_if: "else_clause",
+            },
+        )),
+        LANG::Tsx => Some((
+            tree_sitter_typescript::language_tsx(),
+            &TYPESCRIPT,
+            Rules::Javascript {
+                else_if: "if_statement",
+            },
+        )),
+        // Comments and macros variants do not define any space.
+        LANG::Ccomment | LANG::Preproc => None,
+    }
+}
+
+// Data needed to visit the syntax tree of a source file.
+struct Visitor<'a> {
+    kinds: &'static Kinds,
+    rules: Rules,
+    source: &'a [u8],
+    lines: Vec<&'a str>,
+    suppressions: Vec<SuppressionComment>,
+    header: HeaderSyntax,
+}
+
+// Suppression comment found in the syntax tree.
+struct SuppressionComment {
+    suppression: SuppressionData,
+    // End line of a comment which is not preceded by code on its first line,
+    // so it can apply to the space below it.
+    end_line: Option<usize>,
+}
+
+/// Maximum nesting depth of the blocks contained in a space.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct NestingData {
+    /// Maximum nesting depth.
+    pub depth: usize,
+    /// Line where the maximum nesting depth is reached for the first time.
+    pub line: usize,
+}
+
+// Members of a class-like space.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ClassMembers {
+    // Methods, also when they are only declared.
+    pub(crate) methods: usize,
+    pub(crate) public_methods: usize,
+    pub(crate) public_attributes: usize,
+}
+
+impl ClassMembers {
+    fn add_method(&mut self, public: bool) {
+        self.methods += 1;
+        self.public_methods += usize::from(public);
+    }
+}
+
+// Data of a space obtained by visiting its syntax tree.
+//
+// Subspaces are stored in the same order used by rust-code-analysis, so the
+// data of a subspace can be associated to the relative `FuncSpace`.
+#[derive(Debug, Default)]
+pub(crate) struct SpaceSyntax {
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+    // Columns start from 1 and, like offsets, they are counted in bytes.
+    pub(crate) start_column: usize,
+    pub(crate) end_column: usize,
+    pub(crate) start_byte: usize,
+    pub(crate) end_byte: usize,
+    pub(crate) closure: bool,
+    // Whether the space is test code or it is contained in test code.
+    pub(crate) test: bool,
+    // Hash of the space code, ignoring whitespace and comments.
+    pub(crate) body_hash: Option<[u8; 32]>,
+    pub(crate) nesting: NestingData,
+    // Members of a class-like space, when they are counted on the syntax
+    // tree rather than by rust-code-analysis.
+    pub(crate) members: Option<ClassMembers>,
+    // Increments of the cognitive and the cyclomatic complexities, sorted
+    // by line.
+    pub(crate) increments: Vec<IncrementData>,
+    // Indices of the suppression comments which apply to the space.
+    pub(crate) suppressions: Vec<usize>,
+    pub(crate) spaces: Vec<SpaceSyntax>,
+}
+
+static EMPTY_SYNTAX: SpaceSyntax = SpaceSyntax {
+    start_line: 0,
+    end_line: 0,
+    start_column: 0,
+    end_column: 0,
+    start_byte: 0,
+    end_byte: 0,
+    closure: false,
+    test: false,
+    body_hash: None,
+    nesting: NestingData { depth: 0, line: 0 },
+    members: None,
+    increments: Vec::new(),
+    suppressions: Vec::new(),
+    spaces: Vec::new(),
+};
+
+impl SpaceSyntax {
+    fn new(node: &Node, visitor: &Visitor, test: bool) -> Self {
+        Self {
+            start_line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            start_column: node.start_position().column + 1,
+            end_column: node.end_position().column + 1,
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+            closure: visitor.kinds.closures.contains(&node.kind()),
+            test,
+            body_hash: Some(body_hash(node, visitor.source)),
+            members: (visitor.kinds.members)(node, visitor.source),
+            ..Default::default()
+        }
+    }
+
+    // Retrieves the data of the subspace at a certain index. When the
+    // subspace cannot be found, e.g. because of a syntax error, empty data
+    // are returned.
+    pub(crate) fn subspace(&self, index: usize, space: &FuncSpace) -> &SpaceSyntax {
+        let matches = |syntax: &&SpaceSyntax| {
+            syntax.start_line == space.start_line && syntax.end_line == space.end_line
+        };
+        self.spaces
+            .get(index)
+            .filter(matches)
+            .or_else(|| self.spaces.iter().find(matches))
+            .unwrap_or(&EMPTY_SYNTAX)
+    }
+}
+
+// An `else if` does not increase the nesting level of the `if` it belongs to.
+fn is_else_if(node: &Node) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == "else_clause" || parent.child_by_field_name("alternative") == Some(*node)
+    })
+}
+
+// Collects the suppression comments of a syntax tree. Comments are extra
+// nodes in all the supported grammars.
+fn suppression_comments(root: Node, source: &[u8], lines: &[&str]) -> Vec<SuppressionComment> {
+    let mut comments = Vec::new();
+    let mut cursor = root.walk();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.is_extra() {
+            let start = node.start_position();
+            if let Some(suppression) = node
+                .utf8_text(source)
+                .ok()
+                .and_then(|text| SuppressionData::parse(text, start.row + 1))
+            {
+                let own_line = lines
+                    .get(start.row)
+                    .and_then(|line| line.get(..start.column))
+                    .is_some_and(|code| code.trim().is_empty());
+                comments.push(SuppressionComment {
+                    suppression,
+                    end_line: own_line.then_some(node.end_position().row + 1),
+                });
+            }
+            continue;
+        }
+        stack.extend(node.children(&mut cursor));
+    }
+    comments.sort_by_key(|comment| comment.suppression.line);
+    comments
+}
+
+// Suppressions which apply to a space starting at a certain line, i.e. the
+// ones on the same line and the ones above it, separated from the space only
+// by comments, attributes and blank lines.
+fn suppressions(start_line: usize, visitor: &Visitor) -> Vec<usize> {
+    visitor
+        .suppressions
+        .iter()
+        .enumerate()
+        .filter(|(_, comment)| {
+            comment.suppression.line == start_line
+                || comment.end_line.is_some_and(|end_line| {
+                    end_line < start_line
+                        && visitor
+                            .lines
+                            .get(end_line..start_line - 1)
+                            .is_some_and(|lines| visitor.header.is_header(lines))
+                })
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// Hashes the code of a node, skipping whitespace and comments, so the hash
+// does not change when the code is only reformatted or commented.
+fn body_hash(node: &Node, source: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    let mut update = |code: &[u8]| {
+        let code: Vec<u8> = code
+            .iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .copied()
+            .collect();
+        hasher.update(code);
+    };
+
+    let mut cursor = node.walk();
+    let mut start = node.start_byte();
+    let mut stack = vec![*node];
+    while let Some(node) = stack.pop() {
+        // Comments are extra nodes in all the supported grammars.
+        if node.is_extra() {
+            update(&source[start..node.start_byte()]);
+            start = node.end_byte();
+            continue;
+        }
+        let first = stack.len();
+        stack.extend(node.children(&mut cursor));
+        stack[first..].reverse();
+    }
+    update(&source[start..node.end_byte()]);
+
+    hasher.finalize().into()
+}
+
+// Visits a space, where `scope` is the nesting state inherited from the
+// parent of the space node and `test` is whether the parent is test code.
+fn visit_space(node: Node, visitor: &Visitor, scope: Scope, test: bool) -> SpaceSyntax {
+    let test = test || (visitor.kinds.tests)(&node, visitor.source);
+    let mut syntax = SpaceSyntax::new(&node, visitor, test);
+    let mut explainer = Explainer::new(&node);
+    let scope = explainer.visit(visitor.rules, &node, scope);
+    let mut cursor = node.walk();
+
+    // Children are pushed in reverse order, so nodes are visited in the
+    // same order they appear in the source code.
+    let mut stack: Vec<(Node, usize, Scope, bool)> = node
+        .children(&mut cursor)
+        .map(|child| (child, 0, scope, test))
+        .collect();
+    stack.reverse();
+
+    while let Some((node, depth, scope, test)) = stack.pop() {
+        // Keywords such as `function` or `class` are not spaces.
+        if node.is_named() && visitor.kinds.spaces.contains(&node.kind()) {
+            let mut space = visit_space(node, visitor, scope, test);
+            space.suppressions = suppressions(space.start_line, visitor);
+            syntax.spaces.push(space);
+            continue;
+        }
+        let scope = explainer.visit(visitor.rules, &node, scope);
+        let test = test || (visitor.kinds.tests)(&node, visitor.source);
+
+        let depth = if visitor.kinds.nesting.contains(&node.kind()) && !is_else_if(&node) {
+            depth + 1
+        } else {
+            depth
+        };
+        if depth > syntax.nesting.depth {
+            syntax.nesting = NestingData {
+                depth,
+                line: node.start_position().row + 1,
+            };
+        }
+
+        let first = stack.len();
+        stack.extend(
+            node.children(&mut cursor)
+                .map(|child| (child, depth, scope, test)),
+        );
+        stack[first..].reverse();
+    }
+
+    syntax.increments = explainer.increments;
+    syntax.increments.sort_by_key(|increment| increment.line);
+    syntax
+}
+
+// Parses a source file and retrieves the data of its unit space, together
+// with its suppression comments.
+pub(crate) fn parse(lang: LANG, source_file: &str) -> (SpaceSyntax, Vec<SuppressionData>) {
+    let Some((language, kinds, rules)) = grammar(lang) else {
+        return Default::default();
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(language).is_err() {
+        return Default::default();
+    }
+    parser
+        .parse(source_file, None)
+        .map_or_else(Default::default, |tree| {
+            let lines: Vec<&str> = source_file.lines().collect();
+            let visitor = Visitor {
+                kinds,
+                rules,
+                source: source_file.as_bytes(),
+                suppressions: suppression_comments(
+                    tree.root_node(),
+                    source_file.as_bytes(),
+                    &lines,
+                ),
+                lines,
+                header: HeaderSyntax::new(lang),
+            };
+            let unit = visit_space(tree.root_node(), &visitor, Scope::default(), false);
+            let suppressions = visitor
+                .suppressions
+                .into_iter()
+                .map(|comment| comment.suppression)
+                .collect();
+            (unit, suppressions)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_nesting() {
+        let source = "fn f(x: i32) {
+    if x > 0 {
+        for i in 0..x {
+            if i == 1 {
+            } else if i == 2 {
+                let g = |y: i32| {
+                    if y > 0 {}
+                };
+            }
+        }
+    }
+}
+";
+        let (unit, _) = parse(LANG::Rust, source);
+        let function = &unit.spaces[0];
+
+        assert_eq!(function.nesting.depth, 3);
+        assert_eq!(function.nesting.line, 4);
+        // The closure is a subspace, so its blocks are not considered.
+        assert_eq!(function.spaces[0].start_line, 6);
+        assert_eq!(function.spaces[0].nesting.depth, 1);
+    }
+
+    #[test]
+    fn rust_body_hash() {
+        let hash = |source| parse(LANG::Rust, source).0.spaces[0].body_hash;
+
+        let body = hash("fn f(x: i32) -> i32 {\n    x + 1\n}\n");
+        assert!(body.is_some());
+        // Whitespace and comments do not change the hash.
+        assert_eq!(
+            body,
+            hash("\n\n// Increment.\nfn f(x: i32) -> i32 {\n    /* one */ x+1\n}\n")
+        );
+        assert_ne!(body, hash("fn f(x: i32) -> i32 {\n    x + 2\n}\n"));
+    }
+
+    #[test]
+    fn rust_class_members() {
+        let source = "impl Point {
+    pub fn new() -> Self {
+        Self
+    }
+    fn hidden(&self) {}
+    pub(crate) fn internal() {}
+    pub const ORIGIN: i32 = 0;
+    const HIDDEN: i32 = 1;
+}
+
+trait Shape {
+    const SIDES: usize;
+    type Output;
+    fn area(&self) -> f64;
+    fn name(&self) -> &str {
+        \"shape\"
+    }
+}
+
+impl Shape for Point {
+    const SIDES: usize = 0;
+    type Output = ();
+    fn area(&self) -> f64 {
+        0.
+    }
+}
+
+fn f() {}
+";
+        let (unit, _) = parse(LANG::Rust, source);
+        let members: Vec<Option<(usize, usize, usize)>> = unit
+            .spaces
+            .iter()
+            .map(|space| {
+                space.members.map(|members| {
+                    (
+                        members.methods,
+                        members.public_methods,
+                        members.public_attributes,
+                    )
+                })
+            })
+            .collect();
+
+        // Only a plain `pub` makes an item of an inherent implementation
+        // public, while trait items are always public.
+        assert_eq!(
+            members,
+            [Some((3, 1, 1)), Some((2, 2, 1)), Some((1, 1, 1)), None]
+        );
+    }
+
+    #[test]
+    fn cpp_class_members() {
+        let source = "class Shape {
+    int id;
+public:
+    int width, height;
+    static const int sides = 4;
+    Shape();
+    virtual ~Shape();
+    int area() const { return width * height; }
+    virtual int *pointer() = 0;
+    template <typename T> T get();
+    friend void swap(Shape &a, Shape &b);
+protected:
+    void resize(int w);
+private:
+    int secret();
+};
+
+struct Point {
+    int x;
+    int y;
+    int norm();
+private:
+    int z;
+};
+";
+        let (unit, _) = parse(LANG::Cpp, source);
+        let members: Vec<Option<(usize, usize, usize)>> = unit
+            .spaces
+            .iter()
+            .map(|space| {
+                space.members.map(|members| {
+                    (
+                        members.methods,
+                        members.public_methods,
+                        members.public_attributes,
+                    )
+                })
+            })
+            .collect();
+
+        // Class members are private by default, struct members are public.
+        // Methods are counted at every access level, friends are skipped.
+        assert_eq!(members, [Some((7, 5, 3)), Some((1, 1, 2))]);
+    }
+
+    #[test]
+    fn rust_tests() {
+        let source = "fn f() {}
+
+#[cfg(test)]
+mod tests {
+    fn helper() {}
+}
+
+#[test]
+fn t() {}
+
+#[tokio::test(flavor = \"multi_thread\")]
+// Asynchronous test.
+async fn u() {}
+
+#[cfg(all(test, feature = \"a,b\"))]
+fn v() {}
+
+#[cfg(any(unix, all(windows, test)))]
+fn w() {}
+
+#[cfg(not(test))]
+fn x() {}
+
+#[cfg(feature = \"test\")]
+fn y() {}
+";
+        let (unit, _) = parse(LANG::Rust, source);
+        let tests: Vec<bool> = unit.spaces.iter().map(|space| space.test).collect();
+
+        assert_eq!(tests, [false, true, true, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn suppressions() {
+        let source = "// ccs:allow(cognitive)
+#[inline]
+fn f() {}
+
+fn g() {} // ccs:allow(reason = \"dispatch\")
+
+// ccs:allow(sloc)
+
+fn h() {}
+
+fn i() {
+    let s = \"ccs:allow(cognitive)\";
+}
+";
+        let (unit, suppressions) = parse(LANG::Rust, source);
+        let indices: Vec<&[usize]> = unit
+            .spaces
+            .iter()
+            .map(|space| space.suppressions.as_slice())
+            .collect();
+
+        // Strings are not comments.
+        assert_eq!(suppressions.len(), 3);
+        assert_eq!(suppressions[1].reason.as_deref(), Some("dispatch"));
+        // Blank lines do not separate a suppression from its space.
+        assert_eq!(indices, [&[0][..], &[1], &[2], &[]]);
+    }
+
+    #[test]
+    fn other_languages_tests() {
+        let tests = |lang, source| -> Vec<bool> {
+            parse(lang, source)
+                .0
+                .spaces
+                .iter()
+                .map(|space| space.test)
+                .collect()
+        };
+
+        assert_eq!(
+            tests(
+                LANG::Python,
+                "def f():\n    pass\n\ndef test_f():\n    pass\n\nclass TestF:\n    pass\n"
+            ),
+            [false, true, true]
+        );
+        // Methods are subspaces of their class.
+        let class = &parse(
+            LANG::Java,
+            "class A {\n    void f() {}\n    @Test\n    void g() {}\n    @org.junit.jupiter.params.ParameterizedTest\n    void h() {}\n    @RepeatedTest(3)\n    void i() {}\n    @LoadTest\n    void j() {}\n}\n",
+        )
+        .0
+        .spaces[0];
+        assert!(!class.test);
+        assert_eq!(
+            class
+                .spaces
+                .iter()
+                .map(|space| space.test)
+                .collect::<Vec<bool>>(),
+            [false, true, true, true, false]
+        );
+        assert_eq!(
+            tests(
+                LANG::Javascript,
+                "function f() {}\ndescribe('f', () => {\n  it.only('works', function () {});\n});\n"
+            ),
+            [false, true]
+        );
+    }
+
+    // Checks that each space found by rust-code-analysis is associated to
+    // the space found by the syntax tree visitor, rather than to empty data.
+    fn assert_matched(lang: LANG, source: &str) {
+        fn visit(space: &FuncSpace, syntax: &SpaceSyntax, lang: LANG) {
+            assert_eq!(space.spaces.len(), syntax.spaces.len(), "{lang:?}");
+            for (index, subspace) in space.spaces.iter().enumerate() {
+                let subsyntax = syntax.subspace(index, subspace);
+                assert!(
+                    !std::ptr::eq(subsyntax, &EMPTY_SYNTAX),
+                    "{lang:?}: no syntax for the space at line {}",
+                    subspace.start_line
+                );
+                visit(subspace, subsyntax, lang);
+            }
+        }
+        let space = rust_code_analysis::get_function_spaces(
+            &lang,
+            source.as_bytes().to_vec(),
+            std::path::Path::new("test"),
+            None,
+        )
+        .unwrap();
+        visit(&space, &parse(lang, source).0, lang);
+    }
+
+    #[test]
+    fn spaces_match_rust_code_analysis() {
+        for entry in std::fs::read_dir("data/seahorse/src").unwrap() {
+            let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert_matched(LANG::Rust, &source);
+        }
+        assert_matched(
+            LANG::Python,
+            "class A:\n    def f(self):\n        return lambda x: x\n\ndef g():\n    def h():\n        pass\n",
+        );
+        assert_matched(
+            LANG::Java,
+            "class A {\n    A() {}\n    void f() {}\n    interface B {\n        void g();\n    }\n}\n",
+        );
+        assert_matched(
+            LANG::Cpp,
+            "namespace n {\nclass A {\n    int f() { return 1; }\n};\nstruct B {};\n}\nint g() { return 0; }\n",
+        );
+        let javascript = "class A {\n  f() {}\n}\nfunction g() {\n  const h = () => 1;\n  return function () {};\n}\nfunction* i() {}\n";
+        assert_matched(LANG::Javascript, javascript);
+        assert_matched(LANG::Mozjs, javascript);
+        assert_matched(
+            LANG::Typescript,
+            "interface I {\n  f(): void;\n}\nclass A implements I {\n  f() {}\n}\nconst g = (x: number) => x;\n",
+        );
+        assert_matched(
+            LANG::Tsx,
+            "function C() {\n  const f = () => 1;\n  return <div onClick={() => f()} />;\n}\n",
+        );
+    }
+}
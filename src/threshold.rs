@@ -0,0 +1,226 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::metrics::{Complexity, MiVariant};
+use crate::{Error, Result};
+
+/// Threshold of a complexity metric.
+///
+/// A threshold can be an absolute value or a value relative to the
+/// distribution of the metric over all the analyzed spaces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    /// Absolute value, e.g. `15`.
+    Absolute(f64),
+    /// Percentile of the metric values, e.g. `p95` reports the worst 5%
+    /// of the spaces.
+    Percentile(f64),
+    /// Number of standard deviations from the mean of the metric values,
+    /// e.g. `z2` reports the spaces two standard deviations worse than
+    /// the mean.
+    ZScore(f64),
+}
+
+impl Threshold {
+    /// Whether a threshold depends on the distribution of the metric values.
+    pub const fn is_relative(&self) -> bool {
+        !matches!(self, Self::Absolute(_))
+    }
+
+    /// Checks whether a threshold is valid for a metric.
+    ///
+    /// An absolute threshold must be in the range accepted by the metric,
+    /// which for the maintainability index depends on its variant, while a
+    /// percentile must be in the [0, 100] range.
+    pub fn validate(&self, complexity: Complexity, mi_variant: MiVariant) -> Result<()> {
+        let (valid, range) = match self {
+            Self::Absolute(threshold) => {
+                let range = complexity.threshold_range(mi_variant);
+                (range.contains(threshold), range)
+            }
+            Self::Percentile(percentile) => {
+                let range = 0. ..=100.;
+                (range.contains(percentile), range)
+            }
+            Self::ZScore(z) => (z.is_finite(), f64::MIN..=f64::MAX),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidThreshold(format!(
+                "{complexity} threshold `{self}` MUST be in the {} range",
+                fmt_range(&range)
+            )))
+        }
+    }
+
+    // Computes the absolute threshold of a metric from the values of all
+    // the analyzed spaces.
+    //
+    // When there are no values, the returned threshold cannot be exceeded.
+    pub(crate) fn resolve(&self, complexity: Complexity, values: &mut [f64]) -> f64 {
+        let lower_is_worse = complexity.lower_is_worse();
+        match self {
+            Self::Absolute(threshold) => *threshold,
+            _ if values.is_empty() => {
+                if lower_is_worse {
+                    f64::NEG_INFINITY
+                } else {
+                    f64::INFINITY
+                }
+            }
+            Self::Percentile(percentile) => {
+                // Sort values from the best to the worst one.
+                values.sort_by(|a, b| {
+                    if lower_is_worse {
+                        b.total_cmp(a)
+                    } else {
+                        a.total_cmp(b)
+                    }
+                });
+                // Nearest-rank percentile.
+                let rank = (percentile / 100. * values.len() as f64).ceil() as usize;
+                values[rank.clamp(1, values.len()) - 1]
+            }
+            Self::ZScore(z) => {
+                let len = values.len() as f64;
+                let mean = values.iter().sum::<f64>() / len;
+                let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / len).sqrt();
+                if lower_is_worse {
+                    mean - z * std_dev
+                } else {
+                    mean + z * std_dev
+                }
+            }
+        }
+    }
+}
+
+// Formats a range of thresholds, where the extreme values are unbounded.
+pub(crate) fn fmt_range(range: &RangeInclusive<f64>) -> String {
+    let fmt_bound = |bound: f64| match bound {
+        f64::MIN => "-inf".to_string(),
+        f64::MAX => "+inf".to_string(),
+        bound => bound.to_string(),
+    };
+    format!(
+        "[{}, {}]",
+        fmt_bound(*range.start()),
+        fmt_bound(*range.end())
+    )
+}
+
+impl From<f64> for Threshold {
+    fn from(threshold: f64) -> Self {
+        Self::Absolute(threshold)
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let parse = |value: &str| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| Error::InvalidThreshold(format!("`{s}` is not a valid threshold")))
+        };
+        if let Some(percentile) = s.strip_prefix('p') {
+            parse(percentile).map(Self::Percentile)
+        } else if let Some(z) = s.strip_prefix('z') {
+            parse(z).map(Self::ZScore)
+        } else {
+            parse(s).map(Self::Absolute)
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Absolute(threshold) => write!(f, "{threshold}"),
+            Self::Percentile(percentile) => write!(f, "p{percentile}"),
+            Self::ZScore(z) => write!(f, "z{z}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_thresholds() {
+        assert_eq!("15".parse::<Threshold>().unwrap(), Threshold::Absolute(15.));
+        assert_eq!(
+            "p95".parse::<Threshold>().unwrap(),
+            Threshold::Percentile(95.)
+        );
+        assert_eq!("z2".parse::<Threshold>().unwrap(), Threshold::ZScore(2.));
+        assert!("x2".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn validate_thresholds() {
+        let validate = |threshold: Threshold, complexity| {
+            threshold
+                .validate(complexity, MiVariant::VisualStudio)
+                .is_ok()
+        };
+        assert!(validate(Threshold::Absolute(250.), Complexity::Cyclomatic));
+        assert!(!validate(Threshold::Absolute(-1.), Complexity::Cognitive));
+        assert!(validate(Threshold::Absolute(-1.), Complexity::Composite));
+        assert!(!validate(
+            Threshold::Percentile(101.),
+            Complexity::Cognitive
+        ));
+    }
+
+    #[test]
+    fn validate_mi_thresholds() {
+        let validate = |threshold, mi_variant| {
+            Threshold::Absolute(threshold)
+                .validate(Complexity::MaintainabilityIndex, mi_variant)
+                .is_ok()
+        };
+        // Visual Studio values are normalized to the [0, 100] range.
+        assert!(validate(100., MiVariant::VisualStudio));
+        assert!(!validate(150., MiVariant::VisualStudio));
+        assert!(!validate(-10., MiVariant::VisualStudio));
+        // The other variants might be negative.
+        assert!(validate(150., MiVariant::Original));
+        assert!(!validate(200., MiVariant::Original));
+        assert!(validate(-10., MiVariant::Original));
+        assert!(validate(200., MiVariant::Sei));
+        assert!(!validate(250., MiVariant::Sei));
+    }
+
+    #[test]
+    fn resolve_thresholds() {
+        let mut values: Vec<f64> = (1..=100).map(f64::from).collect();
+
+        assert_eq!(
+            Threshold::Percentile(95.).resolve(Complexity::Cognitive, &mut values),
+            95.
+        );
+        // Lower values of the maintainability index are worse.
+        assert_eq!(
+            Threshold::Percentile(95.).resolve(Complexity::MaintainabilityIndex, &mut values),
+            6.
+        );
+
+        let mut values = vec![2., 4., 4., 4., 5., 5., 7., 9.];
+        assert_eq!(
+            Threshold::ZScore(2.).resolve(Complexity::Cyclomatic, &mut values),
+            9.
+        );
+        assert_eq!(
+            Threshold::Percentile(95.).resolve(Complexity::Cyclomatic, &mut []),
+            f64::INFINITY
+        );
+    }
+}